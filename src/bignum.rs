@@ -0,0 +1,184 @@
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul};
+
+/// An arbitrarily large unsigned integer, for answers that outgrow `u128`. Only supports what
+/// the puzzles need, addition and multiplication.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /// Little endian, without trailing zeros so that zero is empty
+    limbs: Vec<u64>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Number of significant bits
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(last) => self.limbs.len() as u64 * 64 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    fn normalize(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    /// Divides in place by a single limb and returns the remainder
+    fn div_rem_small(&mut self, divisor: u64) -> u64 {
+        let mut rem = 0u128;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / divisor as u128) as u64;
+            rem = cur % divisor as u128;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u64
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        BigUint { limbs: vec![n] }.normalize()
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        BigUint {
+            limbs: vec![n as u64, (n >> 64) as u64],
+        }
+        .normalize()
+    }
+}
+
+impl TryFrom<&BigUint> for u128 {
+    type Error = ();
+
+    fn try_from(n: &BigUint) -> Result<Self, Self::Error> {
+        match n.limbs[..] {
+            [] => Ok(0),
+            [low] => Ok(low as u128),
+            [low, high] => Ok(low as u128 | (high as u128) << 64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, rhs: &BigUint) {
+        if self.limbs.len() < rhs.limbs.len() {
+            self.limbs.resize(rhs.limbs.len(), 0);
+        }
+
+        let mut carry = false;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let (sum, c1) = limb.overflowing_add(rhs.limbs.get(i).copied().unwrap_or(0));
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+            if !carry && i >= rhs.limbs.len() {
+                break;
+            }
+        }
+        if carry {
+            self.limbs.push(1);
+        }
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let mut res = self.clone();
+        res += rhs;
+        res
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    /// Schoolbook multiplication, quadratic in the number of limbs
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        if self.is_zero() || rhs.is_zero() {
+            return BigUint::default();
+        }
+
+        let mut limbs = vec![0u64; self.limbs.len() + rhs.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, &b) in rhs.limbs.iter().enumerate() {
+                let cur = a as u128 * b as u128 + limbs[i + j] as u128 + carry;
+                limbs[i + j] = cur as u64;
+                carry = cur >> 64;
+            }
+            limbs[i + rhs.limbs.len()] = carry as u64;
+        }
+
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Peels off 19 decimal digits at a time, the most that fit into a limb
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut rest = self.clone();
+        let mut chunks = vec![];
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem_small(CHUNK));
+        }
+
+        let mut digits = match chunks.pop() {
+            Some(first) => first.to_string(),
+            None => "0".to_owned(),
+        };
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:019}", chunk));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = BigUint::from(u128::MAX);
+        let one = BigUint::from(1u64);
+        let sum = &a + &one;
+        assert_eq!(sum.bits(), 129);
+        assert_eq!(sum.to_string(), "340282366920938463463374607431768211456");
+        assert_eq!(u128::try_from(&sum), Err(()));
+        assert_eq!(u128::try_from(&a), Ok(u128::MAX));
+
+        let square = &a * &a;
+        assert_eq!(
+            square.to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+        assert_eq!(&square + &(&(&a + &a) + &one), &sum * &sum);
+    }
+
+    #[test]
+    fn zero() {
+        let zero = BigUint::default();
+        assert_eq!(zero, BigUint::from(0u128));
+        assert_eq!(zero.to_string(), "0");
+        assert_eq!(format!("{:>3}", zero), "  0");
+        assert!((&zero * &BigUint::from(5u64)).is_zero());
+    }
+}
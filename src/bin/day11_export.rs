@@ -0,0 +1,37 @@
+//! Writes the day 11 octopus simulation as images, one per step until all octopuses flash at
+//! once.
+//!
+//! Usage: `day11_export [input file] [output directory] [pgm|ppm|gif]`, the input defaults to
+//! `input/2021/day11.txt`, the output to `day11_frames` and the format to `ppm`.
+
+use std::path::PathBuf;
+use std::{env, error::Error, fs};
+
+use aoc2021::day11::{self, ExportOptions, ImageFormat};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2021/day11.txt".to_owned());
+    let dir = PathBuf::from(args.next().unwrap_or_else(|| "day11_frames".to_owned()));
+    let (format, gif) = match args.next().as_deref() {
+        None => (ExportOptions::default().format, false),
+        Some("ppm") => (Some(ImageFormat::Ppm), false),
+        Some("pgm") => (Some(ImageFormat::Pgm), false),
+        Some("gif") => (None, true),
+        Some(other) => return Err(format!("unknown format `{}`", other).into()),
+    };
+
+    let input = fs::read_to_string(&path)?;
+    let field = day11::input_generator(input.trim_end_matches('\n'))?;
+
+    let options = ExportOptions {
+        format,
+        gif,
+        ..ExportOptions::default()
+    };
+    let steps = day11::export(&field, &dir, &options)?;
+    println!("Wrote {} steps to {}", steps, dir.display());
+    Ok(())
+}
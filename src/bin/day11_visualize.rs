@@ -0,0 +1,35 @@
+//! Animates the day 11 octopus field in the terminal until all octopuses flash at once.
+//!
+//! Usage: `day11_visualize [input file] [frame delay in ms] [max steps]`, the input defaults to
+//! `input/2021/day11.txt`, the delay to 100ms and the maximum number of steps to the one of
+//! `SimulationConfig`. Fields that are stuck in a cycle stop once it went round.
+
+use std::time::Duration;
+use std::{env, error::Error, fs, io};
+
+use aoc2021::day11::{self, SimulationConfig};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2021/day11.txt".to_owned());
+    let delay = match args.next() {
+        Some(ms) => Duration::from_millis(ms.parse()?),
+        None => Duration::from_millis(100),
+    };
+
+    let input = fs::read_to_string(&path)?;
+    let mut field = day11::input_generator(input.trim_end_matches('\n'))?;
+    if let Some(steps) = args.next() {
+        let config = SimulationConfig {
+            step_limit: steps.parse()?,
+            ..field.config().clone()
+        };
+        field = field.with_config(config);
+    }
+
+    let steps = day11::visualize(&field, delay, &mut io::stdout().lock())?;
+    println!("Synchronized after {} steps", steps);
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Times day 18 part 2 on the boxed tree and on the flat representation of snailfish numbers.
+//!
+//! Usage: `day18_bench [input file] [runs]`, the input defaults to `input/2021/day18.txt` and
+//! the number of runs to 10. Build with `--release` for meaningful numbers.
+
+use std::time::{Duration, Instant};
+use std::{env, error::Error, fs};
+
+use aoc2021::day18;
+
+/// Fastest of `runs` calls to `f`, together with its result
+fn time<T>(runs: usize, mut f: impl FnMut() -> T) -> (T, Duration) {
+    let mut best = Duration::MAX;
+    let mut result = None;
+    for _ in 0..runs.max(1) {
+        let start = Instant::now();
+        result = Some(f());
+        best = best.min(start.elapsed());
+    }
+    (result.unwrap(), best)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2021/day18.txt".to_owned());
+    let runs = match args.next() {
+        Some(runs) => runs.parse()?,
+        None => 10,
+    };
+
+    let input = fs::read_to_string(&path)?;
+    let input = input.trim_end_matches('\n');
+    let tree = day18::input_generator(input)?;
+    let flat = day18::input_generator_flat2(input)?;
+
+    let (tree_answer, tree_time) = time(runs, || day18::solve_part2(&tree));
+    let (flat_answer, flat_time) = time(runs, || day18::solve_part2_flat(&flat));
+    if tree_answer != flat_answer {
+        return Err(format!("answers differ: {:?} and {:?}", tree_answer, flat_answer).into());
+    }
+
+    println!("{} lines, answer {}", tree.len(), tree_answer?);
+    println!("tree: {:>10.3?}", tree_time);
+    println!("flat: {:>10.3?}", flat_time);
+    println!(
+        "flat is {:.1}x as fast",
+        tree_time.as_secs_f64() / flat_time.as_secs_f64()
+    );
+    Ok(())
+}
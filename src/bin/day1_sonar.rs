@@ -0,0 +1,26 @@
+//! Counts depth increases over a sliding window in a sonar log of any size, reading it line by
+//! line.
+//!
+//! Usage: `day1_sonar [window size] [log file]`, the window defaults to 1 and the log is read
+//! from stdin if no file is given.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::{env, error::Error};
+
+use aoc2021::day1;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let window = match args.next() {
+        Some(window) => window.parse()?,
+        None => 1,
+    };
+
+    let count = match args.next() {
+        Some(path) => day1::count_increases_in(BufReader::new(File::open(path)?), window)?,
+        None => day1::count_increases_in(io::stdin().lock(), window)?,
+    };
+    println!("{}", count);
+    Ok(())
+}
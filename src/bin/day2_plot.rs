@@ -0,0 +1,46 @@
+//! Plots the trajectories of both submarine steering models for a dive plan.
+//!
+//! Usage: `day2_plot [input file] [output file] [no-flying]`, the input defaults to
+//! `input/2021/day2.txt` and the output to `day2.svg`. Output files ending in `.png` are written
+//! as PNG images. With `no-flying`, plans that take a submarine above the surface are rejected.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::{env, error::Error, fs};
+
+use aoc2021::day2::{self, plot, Models};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let path = args
+        .next()
+        .unwrap_or_else(|| "input/2021/day2.txt".to_owned());
+    let output = args.next().unwrap_or_else(|| "day2.svg".to_owned());
+    let no_flying = match args.next().as_deref() {
+        None => false,
+        Some("no-flying") => true,
+        Some(other) => return Err(format!("unknown option `{}`", other).into()),
+    };
+
+    let input = fs::read_to_string(&path)?;
+    let commands = day2::input_generator(input.trim_end_matches('\n'))?;
+
+    let models = Models::default();
+    let mut trajectories = vec![];
+    for (name, trajectory) in models.compare(&commands, no_flying) {
+        trajectories.push((name, trajectory?));
+    }
+    let paths = trajectories
+        .iter()
+        .map(|(name, states)| (*name, states.as_slice()))
+        .collect::<Vec<_>>();
+
+    let mut out = BufWriter::new(File::create(&output)?);
+    if output.ends_with(".png") {
+        plot::png(&paths, &mut out)?;
+    } else {
+        plot::svg(&paths, &mut out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
@@ -1,28 +1,160 @@
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{self, BufRead};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{numbered_lines, ParseError, SolveError};
+
 #[aoc_generator(day1)]
-pub fn input_generator(s: &str) -> Vec<u32> {
-    s.split_ascii_whitespace()
-        .filter_map(|n| n.parse().ok())
+pub fn input_generator(s: &str) -> Result<Vec<u32>, ParseError> {
+    numbered_lines(s)
+        .map(|(line_no, line)| parse_depth(line).map_err(|e| e.on_line(line_no)))
         .collect()
 }
 
+fn parse_depth(line: &str) -> Result<u32, ParseError> {
+    let n = line.trim();
+    n.parse().map_err(|_| ParseError::at(line, n, "a depth"))
+}
+
 #[aoc(day1, part1)]
-pub fn solve_part1(input: &[u32]) -> u32 {
-    input
-        .iter()
-        .zip(input.iter().skip(1))
-        .filter(|(l, r)| l < r)
-        .count() as u32
+pub fn solve_part1(input: &[u32]) -> Result<u32, SolveError> {
+    let count = count_increases(input.iter().copied(), 1);
+    u32::try_from(count).map_err(|_| SolveError::Overflow)
 }
 
 #[aoc(day1, part2)]
-pub fn solve_part2(input: &[u32]) -> u32 {
-    // Sliding windows are a red herring. If we compare (a + b + c) < (b + c + d), then that's
-    // equivalent to a < d
-    input
-        .iter()
-        .zip(input.iter().skip(3))
-        .filter(|(a, d)| a < d)
-        .count() as u32
+pub fn solve_part2(input: &[u32]) -> Result<u32, SolveError> {
+    let count = count_increases(input.iter().copied(), 3);
+    u32::try_from(count).map_err(|_| SolveError::Overflow)
+}
+
+/// Counts how often the sum over a sliding window of `window` depths is larger than the one
+/// before. Only keeps `window` depths in memory, so `depths` can be arbitrarily long.
+///
+/// Sliding windows are a red herring. Two consecutive windows share all but their outer
+/// depths, so comparing (a + b + c) < (b + c + d) is the same as comparing a < d.
+pub fn count_increases(depths: impl Iterator<Item = u32>, window: usize) -> u64 {
+    if window == 0 {
+        return 0;
+    }
+
+    let mut recent = VecDeque::new();
+    let mut count = 0;
+    for depth in depths {
+        if recent.len() == window {
+            let oldest = recent.pop_front().unwrap();
+            if oldest < depth {
+                count += 1;
+            }
+        }
+        recent.push_back(depth);
+    }
+    count
+}
+
+/// Same as `count_increases`, but reads the depths line by line from `reader`. Lines that
+/// aren't depths end the count with an `InvalidData` error wrapping the `ParseError`.
+pub fn count_increases_in(reader: impl BufRead, window: usize) -> io::Result<u64> {
+    let mut error = None;
+    let depths = Depths::new(reader).map_while(|depth| depth.map_err(|e| error = Some(e)).ok());
+    let count = count_increases(depths, window);
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(count),
+    }
+}
+
+/// Parses depths from a reader one line at a time, reusing the line buffer
+pub struct Depths<R> {
+    reader: R,
+    line: String,
+    line_no: usize,
+}
+
+impl<R: BufRead> Depths<R> {
+    pub fn new(reader: R) -> Self {
+        Depths {
+            reader,
+            line: String::new(),
+            line_no: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Depths<R> {
+    type Item = io::Result<u32>;
+
+    fn next(&mut self) -> Option<io::Result<u32>> {
+        self.line.clear();
+        match self.reader.read_line(&mut self.line) {
+            Ok(0) => return None,
+            Ok(_) => self.line_no += 1,
+            Err(e) => return Some(Err(e)),
+        }
+
+        let line = self.line.trim_end_matches(&['\r', '\n'][..]);
+        Some(
+            parse_depth(line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.on_line(self.line_no))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(7));
+        assert_eq!(solve_part2(&input), Ok(5));
+    }
+
+    #[test]
+    fn any_window() {
+        let input = input_generator(EXAMPLE).unwrap();
+        let count = |window| count_increases(input.iter().copied(), window);
+        assert_eq!(count(0), 0);
+        assert_eq!(count(2), 5);
+        assert_eq!(count(9), 1);
+        assert_eq!(count(10), 0);
+        assert_eq!(count(usize::MAX), 0);
+
+        // Brute force over the actual window sums
+        for window in 1..=5 {
+            let sums = input.windows(window).map(|w| w.iter().sum::<u32>());
+            let expected = sums
+                .clone()
+                .zip(sums.skip(1))
+                .filter(|(a, b)| a < b)
+                .count();
+            assert_eq!(count(window), expected as u64, "window {}", window);
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let reader = format!("{}\n", EXAMPLE.replace('\n', "\r\n"));
+        assert_eq!(count_increases_in(reader.as_bytes(), 1).unwrap(), 7);
+        assert_eq!(count_increases_in(reader.as_bytes(), 3).unwrap(), 5);
+        assert_eq!(count_increases_in(&b""[..], 3).unwrap(), 0);
+
+        let err = count_increases_in("199\n200\n\n208".as_bytes(), 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err.into_inner().unwrap().downcast::<ParseError>().unwrap();
+        assert_eq!((err.line, err.column, err.text.as_str()), (3, 1, ""));
+    }
+
+    #[test]
+    fn reports_bad_line() {
+        let err = input_generator("199\n2O0\n208").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.text, "2O0");
+    }
 }
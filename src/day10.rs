@@ -1,5 +1,7 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{numbered_lines, ParseError, SolveError};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum ParenKind {
     Round,
@@ -56,15 +58,24 @@ impl Paren {
 }
 
 #[aoc_generator(day10)]
-pub fn input_generator(s: &str) -> Vec<Vec<Paren>> {
-    s.lines()
-        .map(|l| l.chars().filter_map(Paren::from_char).collect())
+pub fn input_generator(s: &str) -> Result<Vec<Vec<Paren>>, ParseError> {
+    numbered_lines(s)
+        .map(|(line_no, line)| {
+            line.char_indices()
+                .map(|(pos, c)| {
+                    Paren::from_char(c).ok_or_else(|| {
+                        let c = &line[pos..pos + c.len_utf8()];
+                        ParseError::new(pos + 1, c, "a bracket").on_line(line_no)
+                    })
+                })
+                .collect()
+        })
         .collect()
 }
 
 #[aoc(day10, part1)]
-pub fn solve_part1(input: &Vec<Vec<Paren>>) -> usize {
-    input
+pub fn solve_part1(input: &[Vec<Paren>]) -> Result<usize, SolveError> {
+    Ok(input
         .iter()
         .map(|line| {
             let mut stack = vec![];
@@ -86,11 +97,11 @@ pub fn solve_part1(input: &Vec<Vec<Paren>>) -> usize {
 
             0
         })
-        .sum()
+        .sum())
 }
 
 #[aoc(day10, part2)]
-pub fn solve_part2(input: &Vec<Vec<Paren>>) -> usize {
+pub fn solve_part2(input: &[Vec<Paren>]) -> Result<usize, SolveError> {
     let mut scores: Vec<_> = input
         .iter()
         .filter_map(|line| {
@@ -112,14 +123,47 @@ pub fn solve_part2(input: &Vec<Vec<Paren>>) -> usize {
             }
 
             Some(
-            stack
-                .iter()
-                .rev()
-                .fold(0, |acc, kind| acc * 5 + kind.value2())
+                stack
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, kind| acc * 5 + kind.value2()),
             )
         })
         .collect();
 
-    scores.sort();
-    scores[scores.len() / 2]
+    if scores.is_empty() {
+        return Err(SolveError::EmptyInput);
+    }
+
+    scores.sort_unstable();
+    Ok(scores[scores.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "[({(<(())[]>[[{[]{<()<>>
+[(()[<>])]({[<{<<[]>>(
+{([(<{}[<>[]}>{[]{[(<()>
+(((({<>}<{<{<>}{[]{[]{}
+[[<[([]))<([[{}[[()]]]
+[{[{({}]{}}([{[{{{}}([]
+{<[[]]>}<{[{[{[]{()[[[]
+[<(<(<(<{}))><([]([]()
+<{([([[(<>()){}]>(<<{{
+<{([{{}}[<[[[<>{}]]]>[]]";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(26397));
+        assert_eq!(solve_part2(&input), Ok(288957));
+    }
+
+    #[test]
+    fn no_incomplete_lines() {
+        let input = input_generator("(]\n<}").unwrap();
+        assert_eq!(solve_part2(&input), Err(SolveError::EmptyInput));
+    }
 }
@@ -1,137 +1,504 @@
-use std::{collections::HashSet, fmt::Display};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{ParseError, SolveError};
+use crate::grid::{Grid, Point, NEIGHBOURS4, NEIGHBOURS6, NEIGHBOURS8};
+
+mod export;
+mod gif;
+mod visualize;
+pub use export::{export, ExportOptions, ImageFormat};
+pub use visualize::visualize;
+
 #[aoc_generator(day11)]
-pub fn input_generator(s: &str) -> Field {
+pub fn input_generator(s: &str) -> Result<Field, ParseError> {
     Field::from_str(s)
 }
 
 #[aoc(day11, part1)]
-pub fn solve_part1(input: &Field) -> usize {
-    let mut f = input.clone();
-    let mut res = 0;
-    for _ in 0..100 {
-        res += f.step();
-    }
-
-    res
+pub fn solve_part1(input: &Field) -> Result<usize, SolveError> {
+    input.check()?;
+    Ok(input.total_flashes())
 }
 
 #[aoc(day11, part2)]
-pub fn solve_part2(input: &Field) -> usize {
-    let mut f = input.clone();
-    for i in 0.. {
-        // std::thread::sleep(std::time::Duration::from_millis(100));
-        // println!("{}", f);
-        if f.step() == f.data.len() {
-            // println!("{}", f);
-            return i + 1;
+pub fn solve_part2(input: &Field) -> Result<usize, SolveError> {
+    input.check()?;
+    input.synchronize()
+}
+
+/// Which octopuses are charged by a flash
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The 4 horizontally and vertically adjacent ones
+    VonNeumann,
+    /// All 8 adjacent ones, including diagonals, as in the puzzle
+    Moore,
+    /// The 6 adjacent ones when the field is read as a hexagonal grid in axial coordinates, see
+    /// `NEIGHBOURS6`
+    Hex,
+}
+
+impl Neighbourhood {
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Neighbourhood::VonNeumann => &NEIGHBOURS4,
+            Neighbourhood::Moore => &NEIGHBOURS8,
+            Neighbourhood::Hex => &NEIGHBOURS6,
         }
     }
+}
+
+/// Rules of the simulation, the default ones are those of the puzzle
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulationConfig {
+    /// Number of steps `total_flashes` runs for
+    pub steps: usize,
+    /// An octopus flashes once its energy level goes above this
+    pub threshold: i8,
+    pub neighbourhood: Neighbourhood,
+    /// Whether flashes at an edge reach over to the opposite one, as if the field was a torus.
+    /// On a torus less than 3 cells wide or high some neighbours are adjacent from two sides and
+    /// get charged twice.
+    pub wrap: bool,
+    /// Number of steps after which `synchronize` gives up, most fields that never synchronize
+    /// are caught much earlier by their cycle
+    pub step_limit: usize,
+}
 
-    unreachable!()
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            steps: 100,
+            threshold: 9,
+            neighbourhood: Neighbourhood::Moore,
+            wrap: false,
+            step_limit: 1_000_000,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Field {
-    data: Vec<i8>,
-    width: usize,
-    height: usize,
+    grid: Grid<i8>,
+    config: SimulationConfig,
+    /// Scratch space for `step`, kept around so steps don't allocate
+    flashed: Grid<bool>,
+    pending: Vec<Point>,
+    flashes: Vec<Point>,
 }
 
 impl Field {
-    fn from_str(s: &str) -> Field {
-        let mut data = vec![];
-
-        let mut width = 0;
-        let mut height = 0;
-        for l in s.lines() {
-            width = width.max(l.len());
-            data.extend(l.chars().filter_map(|c| c.to_digit(10).map(|i| i as i8)));
-            height += 1;
-        }
+    fn from_str(s: &str) -> Result<Field, ParseError> {
+        let grid = Grid::parse(s, "an energy level", |c| c.to_digit(10).map(|d| d as i8))?;
+        Ok(Field::new(grid))
+    }
 
+    fn new(grid: Grid<i8>) -> Field {
         Field {
-            data,
-            width,
-            height,
+            flashed: grid.map(|_| false),
+            grid,
+            config: SimulationConfig::default(),
+            pending: vec![],
+            flashes: vec![],
         }
     }
 
-    fn get_index(&self, p: (isize, isize)) -> Option<usize> {
-        if p.0 < 0 || p.0 >= self.width as isize || p.1 < 0 || p.1 >= self.height as isize {
-            None
-        } else {
-            Some((p.0 + p.1 * (self.width as isize)) as usize)
+    /// Ensures that all energy levels are between 0 and the configured threshold, an octopus
+    /// that starts out above it can't come from a valid input
+    fn check(&self) -> Result<(), SolveError> {
+        let levels = 0..=self.config.threshold;
+        match self.grid.iter().position(|v| !levels.contains(v)) {
+            Some(idx) => {
+                let (x, y) = self.grid.point_of(idx);
+                Err(SolveError::OutOfRange {
+                    x,
+                    y,
+                    value: self.grid[(x, y)] as i64,
+                })
+            }
+            None => Ok(()),
         }
     }
 
-    fn get(&self, idx: (isize, isize)) -> Option<i8> {
-        self.get_index(idx).map(|idx| self.data[idx])
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
     }
 
-    fn step(&mut self) -> usize {
-        let mut flashes = HashSet::new();
+    pub fn with_config(self, config: SimulationConfig) -> Field {
+        Field { config, ..self }
+    }
 
-        for i in 0..self.width as isize {
-            for j in 0..self.height as isize {
-                self.maybe_flash(&mut flashes, (i, j))
-            }
+    /// Energy level of the octopus at `p`
+    pub fn get(&self, p: Point) -> Option<i8> {
+        self.grid.get(p).copied()
+    }
+
+    /// Number of flashes within the configured number of steps, starting from the current state
+    pub fn total_flashes(&self) -> usize {
+        let mut field = self.clone();
+        (0..self.config.steps).map(|_| field.step().len()).sum()
+    }
+
+    /// Finds the first step in which all octopuses flash at once.
+    ///
+    /// Every state reached so far is remembered by its hash, and a repeated state means the
+    /// simulation is stuck in a cycle that doesn't contain a synchronized step. Hash matches
+    /// are confirmed by replaying up to the earlier state, so collisions can't cause false
+    /// reports. An empty field has nothing to synchronize and fails with
+    /// `SolveError::EmptyInput`.
+    pub fn synchronize(&self) -> Result<usize, SolveError> {
+        if self.grid.is_empty() {
+            return Err(SolveError::EmptyInput);
         }
 
-        for p in flashes.iter() {
-            if let Some(idx) = self.get_index(*p) {
-                self.data[idx] = 0;
+        let mut field = self.clone();
+        let mut seen = HashMap::new();
+        seen.insert(hash(&field.grid), 0);
+
+        for step in 1..=self.config.step_limit {
+            if field.step().len() == field.grid.len() {
+                return Ok(step);
+            }
+
+            match seen.entry(hash(&field.grid)) {
+                Entry::Occupied(earlier) => {
+                    let start = *earlier.get();
+                    if self.state_after(start) == field.grid {
+                        return Err(SolveError::Cycle {
+                            start,
+                            length: step - start,
+                        });
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(step);
+                }
             }
         }
 
-        flashes.len()
+        Err(SolveError::StepLimit(self.config.step_limit))
     }
 
-    fn maybe_flash(&mut self, flashes: &mut HashSet<(isize, isize)>, p: (isize, isize)) {
-        if flashes.contains(&p) {
-            return;
+    fn state_after(&self, steps: usize) -> Grid<i8> {
+        let mut field = self.clone();
+        for _ in 0..steps {
+            field.step();
+        }
+        field.grid
+    }
+
+    /// Advances the simulation by one step and returns the octopuses that flashed, in the
+    /// order they did
+    pub fn step(&mut self) -> &[Point] {
+        self.flashes.clear();
+
+        for p in self.grid.points() {
+            self.charge(p);
         }
 
-        if let Some(idx) = self.get_index(p) {
-            self.data[idx] += 1;
-            if self.data[idx] > 9 {
-                flashes.insert(p);
-                for d_x in -1..=1 {
-                    for d_y in -1..=1 {
-                        let p1 = (p.0 + d_x, p.1 + d_y);
-                        self.maybe_flash(flashes, p1);
-                    }
+        // Flashes are propagated with an explicit stack rather than recursion, a chain
+        // reaction can run across the whole field
+        let offsets = self.config.neighbourhood.offsets();
+        let wrap = self.config.wrap;
+        while let Some(p) = self.pending.pop() {
+            self.flashes.push(p);
+            for &d in offsets {
+                let neighbour = if wrap {
+                    self.grid.offset_wrapping(p, d)
+                } else {
+                    self.grid.offset(p, d)
+                };
+                // An octopus that is its own neighbour on a tiny torus has already flashed, so
+                // `charge` ignores it
+                if let Some(neighbour) = neighbour {
+                    self.charge(neighbour);
                 }
             }
         }
+
+        for &p in self.flashes.iter() {
+            self.grid[p] = 0;
+            self.flashed[p] = false;
+        }
+
+        &self.flashes
+    }
+
+    /// Raises the energy level at `p`, queueing a flash if it goes above the threshold. An
+    /// octopus flashes at most once per step.
+    fn charge(&mut self, p: Point) {
+        if self.flashed[p] {
+            return;
+        }
+
+        // Saturating, with the highest threshold octopuses simply never flash
+        self.grid[p] = self.grid[p].saturating_add(1);
+        if self.grid[p] > self.config.threshold {
+            self.flashed[p] = true;
+            self.pending.push(p);
+        }
+    }
+}
+
+/// Groups energy levels into 5 shades, from 0 for the octopuses that just flashed to 4 for the
+/// ones about to
+fn shade_level(value: i8) -> usize {
+    match value {
+        i8::MIN..=1 => 0,
+        2 | 3 => 1,
+        4 | 5 => 2,
+        6 | 7 => 3,
+        8..=i8::MAX => 4,
     }
 }
 
+/// Block character for an energy level, blank for the octopuses that just flashed
+fn shade(value: i8) -> char {
+    [' ', '░', '▒', '▓', '█'][shade_level(value)]
+}
+
+/// Runs the simulation from the initial state, calling `frame` with the step number, the field
+/// and the octopuses that flashed in that step. Stops after the first step in which all of them
+/// flash, or after `max_steps`, and returns the number of the last step.
+fn replay<E>(
+    field: &Field,
+    max_steps: usize,
+    mut frame: impl FnMut(usize, &Field, &Grid<bool>) -> Result<(), E>,
+) -> Result<usize, E> {
+    let mut field = field.clone();
+    let mut flashed = field.grid.map(|_| false);
+    frame(0, &field, &flashed)?;
+
+    for step in 1..=max_steps {
+        let flashes = field.step().to_vec();
+        for &p in flashes.iter() {
+            flashed[p] = true;
+        }
+        frame(step, &field, &flashed)?;
+        for &p in flashes.iter() {
+            flashed[p] = false;
+        }
+
+        if flashes.len() == field.grid.len() {
+            return Ok(step);
+        }
+    }
+
+    Ok(max_steps)
+}
+
+fn hash(grid: &Grid<i8>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    grid.hash(&mut hasher);
+    hasher.finish()
+}
+
 impl Display for Field {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for i in 0..self.height as isize {
-            for j in 0..self.width as isize {
-                let value = self.get((i, j)).unwrap();
-                let chr = if value == 0 || value == 1 {
-                    ' '
-                } else if value == 3 || value == 2 {
-                    '░'
-                } else if value == 5 || value == 4 {
-                    '▒'
-                } else if value == 7 || value == 6 {
-                    '▓'
-                } else if value == 9 || value == 8 {
-                    '█'
-                } else {
-                    unreachable!();
-                };
-                write!(f, "{}", chr)?
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                write!(f, "{}", shade(self.get((x, y)).unwrap()))?
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) const EXAMPLE: &str = "5483143223
+2745854711
+5264556173
+6141336146
+6357385478
+4167524645
+2176841721
+6882881134
+4846848554
+5283751526";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(1656));
+        assert_eq!(solve_part2(&input), Ok(195));
+    }
+
+    #[test]
+    fn small_example() {
+        let mut field = input_generator("11111\n19991\n19191\n19991\n11111").unwrap();
+        assert_eq!(field.step().len(), 9);
+        assert_eq!(field.to_string(), "░▒▒▒░\n▒   ▒\n▒   ▒\n▒   ▒\n░▒▒▒░\n");
+    }
+
+    #[test]
+    fn non_square() {
+        let mut field = input_generator("000\n009").unwrap();
+        assert_eq!(field.step(), &[(2, 1)]);
+        assert_eq!(field.get((2, 1)), Some(0));
+        assert_eq!(field.get((2, 0)), Some(2));
+        assert_eq!(field.get((0, 2)), None);
+        assert_eq!(field.to_string(), " ░░\n ░ \n");
+
+        let mut field = input_generator("0\n9\n8\n0").unwrap();
+        assert_eq!(field.step(), &[(0, 1), (0, 2)]);
+        assert_eq!(field.to_string(), "░\n \n \n░\n");
+    }
+
+    #[test]
+    fn large_chain_reaction() {
+        let line = "9".repeat(1000);
+        let input = vec![line.as_str(); 1000].join("\n");
+        let mut field = input_generator(&input).unwrap();
+
+        assert_eq!(field.step().len(), 1_000_000);
+        assert!(field.grid.iter().all(|&v| v == 0));
+        assert!(field.step().is_empty());
+        assert!(field.grid.iter().all(|&v| v == 1));
+    }
+
+    #[test]
+    fn configured_steps_and_threshold() {
+        let field = input_generator(EXAMPLE).unwrap();
+        let config = SimulationConfig {
+            steps: 10,
+            ..SimulationConfig::default()
+        };
+        assert_eq!(field.clone().with_config(config).total_flashes(), 204);
+
+        let config = SimulationConfig {
+            steps: 3,
+            threshold: 5,
+            ..SimulationConfig::default()
+        };
+        let mut field = input_generator("5").unwrap().with_config(config.clone());
+        assert_eq!(field.total_flashes(), 1);
+        assert_eq!(field.step(), &[(0, 0)]);
+
+        let config = SimulationConfig {
+            threshold: i8::MAX,
+            ..config
+        };
+        let mut field = input_generator("9").unwrap().with_config(config);
+        for _ in 0..200 {
+            assert!(field.step().is_empty());
+        }
+        assert_eq!(field.get((0, 0)), Some(i8::MAX));
+    }
+
+    #[test]
+    fn checks_levels_against_threshold() {
+        let config = SimulationConfig {
+            threshold: 5,
+            ..SimulationConfig::default()
+        };
+        let input = input_generator("12\n36").unwrap().with_config(config);
+        assert_eq!(
+            solve_part1(&input),
+            Err(SolveError::OutOfRange {
+                x: 1,
+                y: 1,
+                value: 6
+            })
+        );
+
+        let config = SimulationConfig {
+            steps: 10,
+            threshold: 20,
+            ..SimulationConfig::default()
+        };
+        // 9 would be rejected under the default threshold, here it takes 12 steps to flash
+        let input = input_generator("12\n39").unwrap().with_config(config);
+        assert_eq!(solve_part1(&input), Ok(0));
+    }
+
+    #[test]
+    fn neighbourhoods() {
+        let with = |input, neighbourhood, wrap| {
+            let config = SimulationConfig {
+                neighbourhood,
+                wrap,
+                ..SimulationConfig::default()
+            };
+            let mut field = input_generator(input).unwrap().with_config(config);
+            field.step();
+            field.to_string()
+        };
+
+        let centre = "0000\n0900\n0000";
+        assert_eq!(
+            with(centre, Neighbourhood::Moore, false),
+            "░░░ \n░ ░ \n░░░ \n"
+        );
+        assert_eq!(
+            with(centre, Neighbourhood::VonNeumann, false),
+            " ░  \n░ ░ \n ░  \n"
+        );
+        assert_eq!(
+            with(centre, Neighbourhood::Hex, false),
+            " ░░ \n░ ░ \n░░  \n"
+        );
+
+        let corner = "9000\n0000\n0000";
+        assert_eq!(
+            with(corner, Neighbourhood::Moore, false),
+            " ░  \n░░  \n    \n"
+        );
+        assert_eq!(
+            with(corner, Neighbourhood::Moore, true),
+            " ░ ░\n░░ ░\n░░ ░\n"
+        );
+        assert_eq!(
+            with(corner, Neighbourhood::VonNeumann, true),
+            " ░ ░\n░   \n░   \n"
+        );
+    }
+
+    #[test]
+    fn never_synchronizing() {
+        let input = input_generator("0\n9\n9\n1").unwrap();
+        assert_eq!(
+            solve_part2(&input),
+            Err(SolveError::Cycle {
+                start: 0,
+                length: 9
+            })
+        );
+        assert_eq!(
+            solve_part2(&input).unwrap_err().to_string(),
+            "enters cycle of length 9 at step 0 without synchronizing"
+        );
+
+        let config = SimulationConfig {
+            step_limit: 194,
+            ..SimulationConfig::default()
+        };
+        let input = input_generator(EXAMPLE).unwrap().with_config(config);
+        assert_eq!(solve_part2(&input), Err(SolveError::StepLimit(194)));
+    }
+
+    #[test]
+    fn empty_field() {
+        let input = input_generator("").unwrap();
+        assert_eq!(solve_part1(&input), Ok(0));
+        assert_eq!(solve_part2(&input), Err(SolveError::EmptyInput));
+    }
+
+    #[test]
+    fn rejects_ragged_lines() {
+        let err = input_generator("123\n12\n123").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 3));
+
+        let err = input_generator("123\n1234").err().unwrap();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 4, "4"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::grid::Grid;
+
+use super::gif::GifWriter;
+use super::{replay, shade_level, Field};
+
+/// Colours for the 5 shades followed by the one for flashing octopuses, which are yellow like
+/// in the terminal
+const PALETTE: [[u8; 3]; 6] = [
+    [0, 0, 0],
+    [48, 48, 48],
+    [96, 96, 96],
+    [144, 144, 144],
+    [192, 192, 192],
+    [255, 221, 0],
+];
+/// Grey levels for the same shades, flashes are plain white
+const GREYS: [u8; 6] = [0, 48, 96, 144, 192, 255];
+const FLASH: u8 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Binary greyscale netpbm, `.pgm`
+    Pgm,
+    /// Binary colour netpbm, `.ppm`
+    Ppm,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Pgm => "pgm",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    /// Format of the per-step frames, `None` to skip them
+    pub format: Option<ImageFormat>,
+    /// Whether to write all steps into a single looping `animation.gif` as well
+    pub gif: bool,
+    /// Width and height in pixels of a single octopus
+    pub scale: usize,
+    /// Time each step is shown for in the GIF, which counts in hundredths of a second
+    pub frame_delay: Duration,
+    /// Fields that never synchronize would otherwise fill the disk
+    pub max_steps: usize,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            format: Some(ImageFormat::Ppm),
+            gif: false,
+            scale: 8,
+            frame_delay: Duration::from_millis(100),
+            max_steps: 1000,
+        }
+    }
+}
+
+/// Replays the simulation into `dir`, writing one `step_NNNN` image per step starting with the
+/// initial state and optionally an animated GIF of all of them. Stops after the first step in
+/// which all octopuses flash, or after `max_steps`, and returns the number of the last step.
+pub fn export(field: &Field, dir: &Path, options: &ExportOptions) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let scale = options.scale.max(1);
+    let (width, height) = (field.grid.width() * scale, field.grid.height() * scale);
+
+    let mut gif = if options.gif {
+        let file = BufWriter::new(File::create(dir.join("animation.gif"))?);
+        Some(GifWriter::new(file, width, height, &PALETTE)?)
+    } else {
+        None
+    };
+    let delay = (options.frame_delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+
+    let steps = replay(
+        field,
+        options.max_steps,
+        |step, field, flashed| -> io::Result<()> {
+            let pixels = render(field, flashed, scale);
+
+            if let Some(format) = options.format {
+                let path = dir.join(format!("step_{:04}.{}", step, format.extension()));
+                let mut out = BufWriter::new(File::create(path)?);
+                write_netpbm(&pixels, format, &mut out)?;
+                out.flush()?;
+            }
+            if let Some(gif) = gif.as_mut() {
+                gif.frame(pixels.as_slice(), delay)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    if let Some(gif) = gif {
+        gif.finish()?;
+    }
+    Ok(steps)
+}
+
+/// Palette indices for every pixel, each octopus becomes a `scale` by `scale` square
+fn render(field: &Field, flashed: &Grid<bool>, scale: usize) -> Grid<u8> {
+    let grid = &field.grid;
+    let mut pixels = Grid::new(grid.width() * scale, grid.height() * scale, 0);
+    for p in pixels.points() {
+        let cell = (p.0 / scale, p.1 / scale);
+        pixels[p] = if flashed[cell] {
+            FLASH
+        } else {
+            shade_level(grid[cell]) as u8
+        };
+    }
+    pixels
+}
+
+fn write_netpbm(pixels: &Grid<u8>, format: ImageFormat, out: &mut impl Write) -> io::Result<()> {
+    let magic = match format {
+        ImageFormat::Pgm => "P5",
+        ImageFormat::Ppm => "P6",
+    };
+    writeln!(
+        out,
+        "{}\n{} {}\n255",
+        magic,
+        pixels.width(),
+        pixels.height()
+    )?;
+
+    let data: Vec<u8> = match format {
+        ImageFormat::Pgm => pixels.iter().map(|&i| GREYS[i as usize]).collect(),
+        ImageFormat::Ppm => pixels.iter().flat_map(|&i| PALETTE[i as usize]).collect(),
+    };
+    out.write_all(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::day11::gif::count_frames;
+    use crate::day11::{input_generator, tests::EXAMPLE};
+
+    #[test]
+    fn netpbm_frames() {
+        let mut field = input_generator("11111\n19991\n19191\n19991\n11111").unwrap();
+        let mut flashed = field.grid.map(|_| false);
+        for &p in field.step() {
+            flashed[p] = true;
+        }
+        let pixels = render(&field, &flashed, 2);
+        assert_eq!((pixels.width(), pixels.height()), (10, 10));
+        assert_eq!(pixels.row(0), &[1, 1, 2, 2, 2, 2, 2, 2, 1, 1]);
+        assert_eq!(pixels.row(3), &[2, 2, 5, 5, 5, 5, 5, 5, 2, 2]);
+
+        let mut out = vec![];
+        write_netpbm(&pixels, ImageFormat::Pgm, &mut out).unwrap();
+        assert!(out.starts_with(b"P5\n10 10\n255\n"));
+        assert_eq!(out.len(), 13 + 100);
+        assert_eq!(out[13 + 3 * 10 + 2], 255);
+
+        let mut out = vec![];
+        write_netpbm(&pixels, ImageFormat::Ppm, &mut out).unwrap();
+        assert!(out.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(out.len(), 13 + 300);
+        assert_eq!(&out[13..16], &[48, 48, 48]);
+    }
+
+    /// Removes the directory again when the test ends, whether it passes or not
+    struct TempDir(PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn exports_until_synchronization() {
+        let dir = TempDir(
+            std::env::temp_dir().join(format!("aoc2021-day11-export-{}", std::process::id())),
+        );
+        let dir = &dir.0;
+        let field = input_generator(EXAMPLE).unwrap();
+        let options = ExportOptions {
+            format: Some(ImageFormat::Ppm),
+            gif: true,
+            scale: 1,
+            ..ExportOptions::default()
+        };
+        assert_eq!(export(&field, dir, &options).unwrap(), 195);
+
+        let mut names = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names.len(), 196 + 1);
+        assert_eq!(names[0], "animation.gif");
+        assert_eq!(names[1], "step_0000.ppm");
+        assert_eq!(names[196], "step_0195.ppm");
+
+        let gif = fs::read(dir.join("animation.gif")).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x0a\x00\x0a\x00"));
+        assert_eq!(count_frames(&gif), 196);
+    }
+}
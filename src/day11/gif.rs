@@ -0,0 +1,307 @@
+//! A minimal GIF89a encoder for looping animations with a single global palette
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+/// Largest code the LZW compression may use, GIF caps codes at 12 bits
+const MAX_CODE: u16 = 4095;
+
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    min_code_size: u8,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Writes the header, the global colour table and a loop-forever extension. The palette
+    /// holds at most 256 colours, frames index into it.
+    pub fn new(mut out: W, width: usize, height: usize, palette: &[[u8; 3]]) -> io::Result<Self> {
+        assert!(
+            !palette.is_empty() && palette.len() <= 256,
+            "GIF palettes hold 1 to 256 colours"
+        );
+        let dimension = |n: usize| {
+            u16::try_from(n).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} pixels is too large for a GIF", n),
+                )
+            })
+        };
+        let (width, height) = (dimension(width)?, dimension(height)?);
+
+        // The colour table size is stored as a power of two, between 2 and 256 entries
+        let table_bits = (usize::BITS - (palette.len() - 1).leading_zeros()).max(1);
+
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // Global colour table flag, 8 bits of colour resolution and the table size
+        out.write_all(&[0xf0 | (table_bits - 1) as u8, 0, 0])?;
+        for i in 0..1 << table_bits {
+            out.write_all(palette.get(i).unwrap_or(&[0, 0, 0]))?;
+        }
+        // NETSCAPE2.0 application extension, looping forever
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+        Ok(GifWriter {
+            out,
+            width,
+            height,
+            min_code_size: table_bits.max(2) as u8,
+        })
+    }
+
+    /// Appends a frame of palette indices in row-major order, shown for `delay` hundredths of
+    /// a second
+    pub fn frame(&mut self, pixels: &[u8], delay: u16) -> io::Result<()> {
+        assert_eq!(
+            pixels.len(),
+            self.width as usize * self.height as usize,
+            "frame doesn't match the image size"
+        );
+
+        // Graphic control extension, only for the delay
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        self.out.write_all(&delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor covering the whole canvas, without a local colour table
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x00, self.min_code_size])?;
+
+        let data = compress(pixels, self.min_code_size);
+        for block in data.chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x00])
+    }
+
+    /// Writes the trailer and hands back the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Packs variable-width codes into bytes, least significant bit first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size as u32;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Variable-width LZW as used by GIF. The code table is reset with a clear code whenever it
+/// runs full.
+fn compress(pixels: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = BitWriter::default();
+    let mut table = HashMap::new();
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+    out.write(clear, size);
+
+    let mut pixels = pixels.iter();
+    let mut prefix = match pixels.next() {
+        Some(&p) => p as u16,
+        None => {
+            out.write(end, size);
+            return out.finish();
+        }
+    };
+
+    for &p in pixels {
+        if let Some(&code) = table.get(&(prefix, p)) {
+            prefix = code;
+            continue;
+        }
+
+        out.write(prefix, size);
+        if next > MAX_CODE {
+            out.write(clear, size);
+            table.clear();
+            next = end + 1;
+            size = min_code_size + 1;
+        } else {
+            // The decoder widens its codes one entry later, it only learns about `next` from
+            // the following code
+            if next > (1 << size) - 1 && size < 12 {
+                size += 1;
+            }
+            table.insert((prefix, p), next);
+            next += 1;
+        }
+        prefix = p as u16;
+    }
+
+    out.write(prefix, size);
+    // The decoder adds a table entry for this last code as well, which may widen the end code
+    if next > (1 << size) - 1 && size < 12 {
+        size += 1;
+    }
+    out.write(end, size);
+    out.finish()
+}
+
+/// Number of images in a GIF written by `GifWriter`, found by walking its block structure
+#[cfg(test)]
+pub(super) fn count_frames(data: &[u8]) -> usize {
+    let skip_sub_blocks = |mut pos: usize| {
+        while data[pos] != 0 {
+            pos += data[pos] as usize + 1;
+        }
+        pos + 1
+    };
+
+    // Header, screen descriptor and global colour table
+    let mut pos = 13 + 3 * (2 << (data[10] & 0x07));
+    let mut frames = 0;
+    loop {
+        match data[pos] {
+            0x21 => pos = skip_sub_blocks(pos + 2),
+            0x2c => {
+                frames += 1;
+                // Image descriptor and the LZW minimum code size
+                pos = skip_sub_blocks(pos + 11);
+            }
+            0x3b => return frames,
+            other => panic!("unexpected block {:#04x} at {}", other, pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference decoder, following the GIF specification rather than the encoder
+    fn decompress(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut size = min_code_size + 1;
+        let mut prev: Option<usize> = None;
+        let mut out = vec![];
+
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u8, data.iter());
+        loop {
+            while bits < size {
+                buffer |= (*bytes.next().expect("missing end code") as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << size) - 1)) as usize;
+            buffer >>= size;
+            bits -= size;
+
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.extend([vec![], vec![]]);
+                size = min_code_size + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+
+            let entry = match (table.get(code), prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => {
+                    let mut entry = table[prev].clone();
+                    entry.push(table[prev][0]);
+                    entry
+                }
+                (None, None) => panic!("unknown code {}", code),
+            };
+            if let Some(prev) = prev {
+                let mut new = table[prev].clone();
+                new.push(entry[0]);
+                table.push(new);
+                if table.len() == 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            out.extend_from_slice(&entry);
+            prev = Some(code);
+        }
+    }
+
+    #[test]
+    fn compress_round_trip() {
+        let mut state = 0x2545_f491u32;
+        let mut noise = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % 6) as u8
+        };
+
+        let inputs = vec![
+            vec![],
+            vec![3],
+            vec![1; 10_000],
+            (0..20_000).map(|i| (i / 7 % 5) as u8).collect(),
+            (0..50_000).map(|_| noise()).collect::<Vec<_>>(),
+        ];
+        for pixels in inputs {
+            assert_eq!(decompress(&compress(&pixels, 3), 3), pixels);
+        }
+        let pixels = (0..=255).cycle().take(5000).collect::<Vec<_>>();
+        assert_eq!(decompress(&compress(&pixels, 8), 8), pixels);
+    }
+
+    #[test]
+    fn widens_end_code() {
+        // The last pixel takes the table to 8 entries, so the end code needs 4 bits. Expected
+        // bytes from an independent decoder.
+        let pixels = [0, 0, 1, 0, 2, 0, 3, 1, 1, 2, 1];
+        assert_eq!(
+            compress(&pixels, 2),
+            vec![0x04, 0x02, 0x02, 0x13, 0x21, 0x51, 0x00]
+        );
+    }
+
+    #[test]
+    fn layout() {
+        let palette = [[0, 0, 0], [255, 255, 255], [255, 0, 0]];
+        let mut gif = GifWriter::new(vec![], 2, 3, &palette).unwrap();
+        gif.frame(&[0, 1, 2, 2, 1, 0], 10).unwrap();
+        let data = gif.finish().unwrap();
+
+        assert!(data.starts_with(b"GIF89a\x02\x00\x03\x00\xf1"));
+        // 4 colour table entries follow the screen descriptor
+        assert_eq!(&data[13..25], &[0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 0]);
+        assert_eq!(&data[25..28], b"\x21\xff\x0b");
+        assert_eq!(data.last(), Some(&0x3b));
+
+        assert!(GifWriter::new(vec![], 70_000, 1, &palette).is_err());
+    }
+}
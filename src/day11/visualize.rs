@@ -0,0 +1,101 @@
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::SolveError;
+use crate::grid::Grid;
+
+use super::{replay, shade, Field};
+
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const RESET: &str = "\x1b[0m";
+/// Bold bright yellow, for the octopuses that flashed in the current step
+const FLASH: &str = "\x1b[1;93m";
+
+/// Replays the simulation on an ANSI terminal, drawing every step in place and waiting `delay`
+/// between frames. Stops after the first step in which all octopuses flash and returns its
+/// number.
+///
+/// Fields that don't synchronize are shown until `Field::synchronize` gives up, which is once
+/// the cycle they are stuck in went round or after the configured step limit. Its error is
+/// then returned as an `io::Error` of kind `Other`.
+pub fn visualize(field: &Field, delay: Duration, out: &mut impl Write) -> io::Result<usize> {
+    let synchronized = field.synchronize();
+    let last = match synchronized {
+        Ok(step) => step,
+        Err(SolveError::Cycle { start, length }) => start + length,
+        Err(SolveError::StepLimit(steps)) => steps,
+        Err(_) => 0,
+    };
+
+    write!(out, "{}{}", CLEAR_SCREEN, HIDE_CURSOR)?;
+    replay(field, last, |step, field, flashed| {
+        if step > 0 {
+            thread::sleep(delay);
+        }
+        draw(field, flashed, step, out)
+    })?;
+    write!(out, "{}", SHOW_CURSOR)?;
+    out.flush()?;
+
+    synchronized.map_err(io::Error::other)
+}
+
+fn draw(field: &Field, flashed: &Grid<bool>, step: usize, out: &mut impl Write) -> io::Result<()> {
+    let flashes = flashed.iter().filter(|&&f| f).count();
+    write!(out, "{}", CURSOR_HOME)?;
+    writeln!(out, "Step {:5}: {:5} flashes", step, flashes)?;
+
+    for y in 0..field.grid.height() {
+        for x in 0..field.grid.width() {
+            if flashed[(x, y)] {
+                write!(out, "{}█{}", FLASH, RESET)?;
+            } else {
+                write!(out, "{}", shade(field.grid[(x, y)]))?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day11::{input_generator, tests::EXAMPLE};
+
+    #[test]
+    fn stops_at_synchronization() {
+        let field = input_generator(EXAMPLE).unwrap();
+        let mut out = vec![];
+        assert_eq!(visualize(&field, Duration::ZERO, &mut out).unwrap(), 195);
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(CLEAR_SCREEN));
+        assert!(out.ends_with(SHOW_CURSOR));
+        assert_eq!(out.matches(CURSOR_HOME).count(), 196);
+        assert!(out.contains("Step   195:   100 flashes"));
+    }
+
+    #[test]
+    fn stops_in_cycle() {
+        let field = input_generator("0\n9\n9\n1").unwrap();
+        let mut out = vec![];
+        let err = visualize(&field, Duration::ZERO, &mut out).unwrap_err();
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<SolveError>(),
+            Some(&SolveError::Cycle {
+                start: 0,
+                length: 9
+            })
+        );
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with(SHOW_CURSOR));
+        assert_eq!(out.matches(CURSOR_HOME).count(), 10);
+    }
+}
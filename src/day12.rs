@@ -2,18 +2,32 @@ use std::collections::{HashMap, HashSet};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{numbered_lines, ParseError, SolveError};
+
 #[aoc_generator(day12)]
-pub fn input_generator(s: &str) -> Graph {
+pub fn input_generator(s: &str) -> Result<Graph, ParseError> {
     let mut graph = Graph::new();
-    for (l, r) in s.lines().filter_map(|l| l.split_once("-")) {
+    for (line_no, line) in numbered_lines(s) {
+        let (l, r) = line
+            .split_once('-')
+            .ok_or_else(|| ParseError::new(1, line, "an edge like `start-A`").on_line(line_no))?;
+
+        for cave in [l, r] {
+            if cave.is_empty() || !cave.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(ParseError::at(line, cave, "a cave name").on_line(line_no));
+            }
+        }
+
         graph.add_edge(l, r);
     }
 
-    graph
+    Ok(graph)
 }
 
 #[aoc(day12, part1)]
-pub fn solve_part1(graph: &Graph) -> usize {
+pub fn solve_part1(graph: &Graph) -> Result<usize, SolveError> {
+    graph.check()?;
+
     // let mut path = HashSet::new();
     // path.insert("start".to_owned());
     // do_walk("start".to_owned(), graph, path)
@@ -21,37 +35,49 @@ pub fn solve_part1(graph: &Graph) -> usize {
     path_count.entry("start".to_owned()).or_insert(1);
 
     let path = vec!["start".to_owned()];
-    do_walk2("start", graph, path, &mut path_count, &|node, counts| {
-        node != "start" && (is_large_cave(node) || counts[node] < 1)
-    })
+    Ok(do_walk2(
+        "start",
+        graph,
+        path,
+        &mut path_count,
+        &|node, counts| node != "start" && (is_large_cave(node) || counts[node] < 1),
+    ))
 }
 
 #[aoc(day12, part2)]
-pub fn solve_part2(graph: &Graph) -> usize {
+pub fn solve_part2(graph: &Graph) -> Result<usize, SolveError> {
+    graph.check()?;
+
     let mut path_count: HashMap<String, usize> = HashMap::new();
     path_count.entry("start".to_owned()).or_insert(1);
 
     let path = vec!["start".to_owned()];
-    do_walk2("start", graph, path, &mut path_count, &|node, counts| {
-        if node == "start" {
-            return false;
-        }
-        if is_large_cave(node) {
-            return true;
-        }
-        let count = counts[node];
-        if count > 2 {
-            return false;
-        }
-        if count == 0 {
-            return true;
-        }
-        if count == 1 {
-            return counts.iter().all(|(node, n)| is_large_cave(node) || *n < 2);
-        }
-        return false;
-        // unreachable!("Node: {} Counts: {:?}", node, counts)
-    })
+    Ok(do_walk2(
+        "start",
+        graph,
+        path,
+        &mut path_count,
+        &|node, counts| {
+            if node == "start" {
+                return false;
+            }
+            if is_large_cave(node) {
+                return true;
+            }
+            let count = counts[node];
+            if count > 2 {
+                return false;
+            }
+            if count == 0 {
+                return true;
+            }
+            if count == 1 {
+                return counts.iter().all(|(node, n)| is_large_cave(node) || *n < 2);
+            }
+            false
+            // unreachable!("Node: {} Counts: {:?}", node, counts)
+        },
+    ))
 }
 
 fn do_walk2<F: Fn(&str, &HashMap<String, usize>) -> bool>(
@@ -61,9 +87,6 @@ fn do_walk2<F: Fn(&str, &HashMap<String, usize>) -> bool>(
     path_count: &mut HashMap<String, usize>,
     check_cave: &F,
 ) -> usize {
-    if node == "end" {
-        panic!("{:?}", path_count);
-    }
     let mut res = 0;
     for out in graph.edges[node].iter() {
         if out == "end" {
@@ -73,7 +96,7 @@ fn do_walk2<F: Fn(&str, &HashMap<String, usize>) -> bool>(
         }
         // println!("...{}", out);
         path_count.entry(out.to_owned()).or_default();
-        if !check_cave(out, &path_count) {
+        if !check_cave(out, path_count) {
             continue;
         }
 
@@ -99,6 +122,34 @@ impl Graph {
         }
     }
 
+    fn check(&self) -> Result<(), SolveError> {
+        for node in ["start", "end"] {
+            if !self.edges.contains_key(node) {
+                return Err(SolveError::MissingNode(node));
+            }
+        }
+
+        // Going back and forth between two large caves would never end. Sorted, so that the
+        // error doesn't depend on the hash map's order.
+        let mut large = self
+            .edges
+            .keys()
+            .filter(|n| is_large_cave(n))
+            .collect::<Vec<_>>();
+        large.sort();
+        for from in large {
+            let neighbours = self.edges[from].iter().filter(|n| is_large_cave(n));
+            if let Some(to) = neighbours.min() {
+                return Err(SolveError::UnboundedPaths {
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     fn add_edge(&mut self, l: &str, r: &str) {
         self.edges
             .entry(l.to_owned())
@@ -114,3 +165,46 @@ impl Graph {
 fn is_large_cave(n: &str) -> bool {
     n.chars().next().unwrap().is_ascii_uppercase()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_example() {
+        let input = input_generator("start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end").unwrap();
+        assert_eq!(solve_part1(&input), Ok(10));
+        assert_eq!(solve_part2(&input), Ok(36));
+    }
+
+    #[test]
+    fn larger_example() {
+        let input = input_generator(
+            "dc-end\nHN-start\nstart-kj\ndc-start\ndc-HN\nLN-dc\nHN-end\nkj-sa\nkj-HN\nkj-dc",
+        )
+        .unwrap();
+        assert_eq!(solve_part1(&input), Ok(19));
+        assert_eq!(solve_part2(&input), Ok(103));
+    }
+
+    #[test]
+    fn missing_start() {
+        let input = input_generator("A-b\nb-end").unwrap();
+        assert_eq!(solve_part1(&input), Err(SolveError::MissingNode("start")));
+    }
+
+    #[test]
+    fn adjacent_large_caves() {
+        let unbounded = |from: &str, to: &str| SolveError::UnboundedPaths {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        };
+
+        let input = input_generator("start-A\nB-A\nB-end").unwrap();
+        assert_eq!(solve_part1(&input), Err(unbounded("A", "B")));
+        assert_eq!(solve_part2(&input), Err(unbounded("A", "B")));
+
+        let input = input_generator("start-b\nb-C\nC-C\nb-end").unwrap();
+        assert_eq!(solve_part1(&input), Err(unbounded("C", "C")));
+    }
+}
@@ -1,40 +1,197 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+use std::num::NonZeroUsize;
 use std::ops::Add;
+use std::str::FromStr;
+use std::{panic, thread};
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{numbered_lines, ParseError, SolveError};
+
+mod flat;
+pub use flat::FlatSnailNumber;
+
 #[aoc_generator(day18)]
-pub fn input_generator(s: &str) -> Vec<SnailNumber> {
-    s.lines()
-        .filter_map(|line| SnailNumber::from_str(line).map(|(n, _)| n))
-        .collect()
+pub fn input_generator(s: &str) -> Result<Vec<SnailNumber>, ParseError> {
+    parse_lines(s)
 }
 
 #[aoc(day18, part1)]
-pub fn solve_part1(numbers: &Vec<SnailNumber>) -> u32 {
-    let mut iter = numbers.iter();
-    let mut first = iter.next().unwrap().clone();
-    first.reduce();
-    iter.fold(first.clone(), |a, b| {
-        let res = &a + b;
-        // println!("Res: {:?} + {:?} =\nRes: {:?}", a, b, res);
-        res
-    })
-    .value()
+pub fn solve_part1(numbers: &[SnailNumber]) -> Result<u32, SolveError> {
+    total_magnitude(numbers)
 }
 
 #[aoc(day18, part2)]
-pub fn solve_part2(numbers: &Vec<SnailNumber>) -> u32 {
-	let mut res = 0;
-	for l in numbers.iter() {
-		for r in numbers.iter() {
-			if l != r {
-				res = res.max((l + r).value());
-			}
-		}
-	}
+pub fn solve_part2(numbers: &[SnailNumber]) -> Result<u32, SolveError> {
+    largest_magnitude(numbers)
+}
+
+#[aoc_generator(day18, part1, Flat)]
+pub fn input_generator_flat1(s: &str) -> Result<Vec<FlatSnailNumber>, ParseError> {
+    parse_lines(s)
+}
+
+#[aoc(day18, part1, Flat)]
+pub fn solve_part1_flat(numbers: &[FlatSnailNumber]) -> Result<u32, SolveError> {
+    total_magnitude(numbers)
+}
+
+#[aoc_generator(day18, part2, Flat)]
+pub fn input_generator_flat2(s: &str) -> Result<Vec<FlatSnailNumber>, ParseError> {
+    parse_lines(s)
+}
+
+#[aoc(day18, part2, Flat)]
+pub fn solve_part2_flat(numbers: &[FlatSnailNumber]) -> Result<u32, SolveError> {
+    largest_magnitude(numbers)
+}
+
+/// The operations the solvers need, so they can run on either representation. Regular numbers
+/// can be as large as a `u32`, so all of them fail with `SolveError::Overflow` once a result
+/// doesn't fit anymore.
+pub trait Snail: Clone + FromStr<Err = ParseError> {
+    fn reduce(&mut self) -> Result<(), SolveError>;
+    fn value(&self) -> Result<u32, SolveError>;
+    /// The reduced sum of `self` and `rhs`
+    fn checked_add(&self, rhs: &Self) -> Result<Self, SolveError>;
+}
+
+impl Snail for SnailNumber {
+    fn reduce(&mut self) -> Result<(), SolveError> {
+        SnailNumber::reduce(self)
+    }
+
+    fn value(&self) -> Result<u32, SolveError> {
+        SnailNumber::value(self)
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Result<Self, SolveError> {
+        SnailNumber::checked_add(self, rhs)
+    }
+}
+
+impl Snail for FlatSnailNumber {
+    fn reduce(&mut self) -> Result<(), SolveError> {
+        FlatSnailNumber::reduce(self)
+    }
+
+    fn value(&self) -> Result<u32, SolveError> {
+        FlatSnailNumber::value(self)
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Result<Self, SolveError> {
+        FlatSnailNumber::checked_add(self, rhs)
+    }
+}
+
+/// Magnitude of a pair from those of its halves
+fn magnitude(left: u32, right: u32) -> Result<u32, SolveError> {
+    left.checked_mul(3)
+        .zip(right.checked_mul(2))
+        .and_then(|(l, r)| l.checked_add(r))
+        .ok_or(SolveError::Overflow)
+}
+
+fn parse_lines<N: Snail>(s: &str) -> Result<Vec<N>, ParseError> {
+    numbered_lines(s)
+        .map(|(line_no, line)| line.parse().map_err(|e: ParseError| e.on_line(line_no)))
+        .collect()
+}
+
+fn total_magnitude<N: Snail>(numbers: &[N]) -> Result<u32, SolveError> {
+    let mut iter = numbers.iter();
+    let mut first = iter.next().ok_or(SolveError::EmptyInput)?.clone();
+    first.reduce()?;
+    iter.try_fold(first, |a, b| a.checked_add(b))?.value()
+}
+
+/// Largest magnitude of the sum of two different lines (which may still hold equal numbers).
+/// The rows of the pairwise comparison are spread over all available cores.
+fn largest_magnitude<N: Snail + Sync>(numbers: &[N]) -> Result<u32, SolveError> {
+    if numbers.len() < 2 {
+        return Err(SolveError::EmptyInput);
+    }
+
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(numbers.len());
+
+    let row_max = |i: usize| {
+        let l = &numbers[i];
+        numbers
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .try_fold(0, |best, (_, r)| Ok(best.max(l.checked_add(r)?.value()?)))
+    };
+
+    // Rows are interleaved rather than chunked, so every thread gets a similar mix of easy and
+    // hard additions
+    let res = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|first| {
+                scope.spawn(move || {
+                    (first..numbers.len())
+                        .step_by(threads)
+                        .try_fold(0, |best, i| Ok(best.max(row_max(i)?)))
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .map(|w| w.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .try_fold(0, |best, row| Ok(best.max(row?)))
+    });
+
+    res
+}
+
+/// Which half of a pair to descend into, from the root of a snailfish number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// A single action taken while reducing a snailfish number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReductionStep {
+    /// The pair `[left,right]` at `path` exploded
+    Explode {
+        path: Vec<Side>,
+        left: u32,
+        right: u32,
+    },
+    /// `value` at `path` was split into a pair
+    Split { path: Vec<Side>, value: u32 },
+}
+
+impl Display for ReductionStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (path, action) = match self {
+            ReductionStep::Explode { path, left, right } => {
+                (path, format!("explode [{},{}]", left, right))
+            }
+            ReductionStep::Split { path, value } => (path, format!("split {}", value)),
+        };
+
+        write!(f, "{} at ", action)?;
+        if path.is_empty() {
+            return write!(f, "root");
+        }
+        for side in path {
+            write!(f, "{}", if *side == Side::Left { 'L' } else { 'R' })?;
+        }
+        Ok(())
+    }
+}
 
-	res
+/// An exploded pair and the parts of it that still have to be added to the neighbouring
+/// regular numbers
+struct Explosion {
+    pair: (u32, u32),
+    carry: (Option<u32>, Option<u32>),
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -45,141 +202,499 @@ pub enum SnailNumber {
 use SnailNumber::*;
 
 impl SnailNumber {
-    fn from_str(remainder: &str) -> Option<(SnailNumber, &str)> {
-        let first = remainder.chars().nth(0)?;
-
-        if first.is_digit(10) {
-            if let Some(first_non_digit) = remainder.find(|c: char| !c.is_digit(10)) {
-                let (number, remainder) = remainder.split_at(first_non_digit);
-                let number = number.parse().ok()?;
-                Some((Value(number), remainder))
-            } else {
-                None
-            }
+    /// Parses a number from the start of `remainder` and returns it together with the unparsed
+    /// rest. On failure, the error is the rest of the input starting at the offending character.
+    fn parse_prefix(remainder: &str) -> Result<(SnailNumber, &str), &str> {
+        let first = remainder.chars().next().ok_or(remainder)?;
+
+        if first.is_ascii_digit() {
+            let first_non_digit = remainder
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(remainder.len());
+            let (number, rest) = remainder.split_at(first_non_digit);
+            let number = number.parse().map_err(|_| remainder)?;
+            Ok((Value(number), rest))
         } else {
             if first != '[' {
-                return None;
+                return Err(remainder);
             }
-            let (left, remainder) = SnailNumber::from_str(&remainder[1..])?;
-            if remainder.chars().nth(0) != Some(',') {
-                return None;
+            let (left, remainder) = SnailNumber::parse_prefix(&remainder[1..])?;
+            if !remainder.starts_with(',') {
+                return Err(remainder);
             }
-            let (right, remaining) = SnailNumber::from_str(&remainder[1..])?;
-            if remaining.chars().nth(0) != Some(']') {
-                return None;
+            let (right, remaining) = SnailNumber::parse_prefix(&remainder[1..])?;
+            if !remaining.starts_with(']') {
+                return Err(remaining);
             }
-            Some((Branch(Box::new(left), Box::new(right)), &remaining[1..]))
+            Ok((Branch(Box::new(left), Box::new(right)), &remaining[1..]))
         }
     }
 
-    fn value(&self) -> u32 {
+    fn value(&self) -> Result<u32, SolveError> {
         match self {
-            Branch(l, r) => l.value() * 3 + r.value() * 2,
-            Value(v) => *v,
+            Branch(l, r) => magnitude(l.value()?, r.value()?),
+            Value(v) => Ok(*v),
         }
     }
 
-    fn explode(&mut self) -> bool {
-        self.do_explode(0).is_some()
+    fn explode(&mut self) -> Result<Option<ReductionStep>, SolveError> {
+        let mut path = vec![];
+        Ok(self
+            .do_explode(&mut path)?
+            .map(|explosion| ReductionStep::Explode {
+                path,
+                left: explosion.pair.0,
+                right: explosion.pair.1,
+            }))
     }
 
-    fn do_explode(&mut self, depth: usize) -> Option<(Option<u32>, Option<u32>)> {
+    /// Explodes the leftmost pair nested inside four pairs. `path` leads to `self` and is left
+    /// pointing at the exploded pair.
+    fn do_explode(&mut self, path: &mut Vec<Side>) -> Result<Option<Explosion>, SolveError> {
         match self {
-            Branch(left, right) if depth >= 4 && left.is_value() && right.is_value() => {
-                let left = left.value();
-                let right = right.value();
+            Branch(left, right) if path.len() >= 4 && left.is_value() && right.is_value() => {
+                let left = left.value()?;
+                let right = right.value()?;
                 *self = Value(0);
-                Some((Some(left), Some(right)))
+                Ok(Some(Explosion {
+                    pair: (left, right),
+                    carry: (Some(left), Some(right)),
+                }))
             }
             Branch(left, right) => {
-                if let Some(todo) = left.do_explode(depth + 1) {
-                    if let Some(value) = todo.1 {
-                        right.add_leftmost(value);
-                        Some((todo.0, None))
-                    } else {
-                        Some(todo)
+                path.push(Side::Left);
+                if let Some(mut todo) = left.do_explode(path)? {
+                    if let Some(value) = todo.carry.1.take() {
+                        right.add_leftmost(value)?;
                     }
-                } else if let Some(todo) = right.do_explode(depth + 1) {
-                    if let Some(value) = todo.0 {
-                        left.add_rightmost(value);
-                        Some((None, todo.1))
-                    } else {
-                        Some(todo)
+                    return Ok(Some(todo));
+                }
+                path.pop();
+
+                path.push(Side::Right);
+                if let Some(mut todo) = right.do_explode(path)? {
+                    if let Some(value) = todo.carry.0.take() {
+                        left.add_rightmost(value)?;
                     }
-                } else {
-                    None
+                    return Ok(Some(todo));
                 }
+                path.pop();
+
+                Ok(None)
             }
-            _ => None,
+            _ => Ok(None),
         }
     }
 
-    fn split(&mut self) -> bool {
+    fn split(&mut self) -> Option<ReductionStep> {
+        let mut path = vec![];
+        let value = self.do_split(&mut path)?;
+        Some(ReductionStep::Split { path, value })
+    }
+
+    /// Splits the leftmost regular number of at least 10 and returns it. `path` leads to `self`
+    /// and is left pointing at the new pair.
+    fn do_split(&mut self, path: &mut Vec<Side>) -> Option<u32> {
         match self {
-            Branch(left, right) => left.split() || right.split(),
+            Branch(left, right) => {
+                for (side, child) in [(Side::Left, left), (Side::Right, right)] {
+                    path.push(side);
+                    if let Some(value) = child.do_split(path) {
+                        return Some(value);
+                    }
+                    path.pop();
+                }
+                None
+            }
             Value(value) if *value >= 10 => {
-                let left = *value / 2;
-                let right = (*value + 1) / 2;
+                let value = *value;
+                let left = value / 2;
+                let right = value.div_ceil(2);
                 *self = Branch(Box::new(Value(left)), Box::new(Value(right)));
-                true
+                Some(value)
             }
-            _ => false,
+            _ => None,
         }
     }
 
-    fn add_leftmost(&mut self, value: u32) {
+    fn add_leftmost(&mut self, value: u32) -> Result<(), SolveError> {
         match self {
             Branch(left, _right) => left.add_leftmost(value),
-            Value(old_value) => *old_value += value,
-        };
+            Value(old_value) => add_to(old_value, value),
+        }
     }
 
-    fn add_rightmost(&mut self, value: u32) {
+    fn add_rightmost(&mut self, value: u32) -> Result<(), SolveError> {
         match self {
             Branch(_left, right) => right.add_rightmost(value),
-            Value(old_value) => *old_value += value,
-        };
+            Value(old_value) => add_to(old_value, value),
+        }
     }
 
-    fn reduce(&mut self) {
-        // println!("Before reduce: {:?}", self);
-        loop {
-            if !self.explode() {
-                if !self.split() {
-                    break;
-                } else {
-                    // println!("After split: {:?}", self);
-                }
-            } else {
-                // println!("After explode: {:?}", self);
-            }
+    fn reduce(&mut self) -> Result<(), SolveError> {
+        self.reduce_with(&mut |_, _| {})
+    }
+
+    /// Reduces the number like `reduce`, calling `trace` after every single explode or split
+    /// with the step and the number as it is afterwards
+    pub fn reduce_with(
+        &mut self,
+        trace: &mut impl FnMut(&ReductionStep, &SnailNumber),
+    ) -> Result<(), SolveError> {
+        while let Some(step) = self.explode()?.or_else(|| self.split()) {
+            trace(&step, self);
         }
+        Ok(())
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Result<Self, SolveError> {
+        let mut res = Branch(Box::new(self.clone()), Box::new(rhs.clone()));
+        res.reduce()?;
+        Ok(res)
     }
 
     fn is_value(&self) -> bool {
-        if let Value(_) = self {
-            true
-        } else {
-            false
-        }
+        matches!(self, Value(_))
     }
 }
 
+/// Adds a regular number carried over by an explosion
+fn add_to(regular: &mut u32, value: u32) -> Result<(), SolveError> {
+    *regular = regular.checked_add(value).ok_or(SolveError::Overflow)?;
+    Ok(())
+}
+
+/// The reduced sum, for numbers that are known to be reduced already
 impl Add for &SnailNumber {
     type Output = SnailNumber;
 
+    /// # Panics
+    ///
+    /// If a regular number overflows while reducing. Reduced operands never get there, use
+    /// `Snail::checked_add` for arbitrary parsed numbers.
     fn add(self, rhs: Self) -> Self::Output {
-        let mut res = Branch(Box::new(self.clone()), Box::new(rhs.clone()));
-        res.reduce();
-        res
+        self.checked_add(rhs).expect("regular number overflowed")
+    }
+}
+
+/// Parses a single snailfish number in the puzzle's `[a,b]` notation, the exact inverse of
+/// `Display`. Errors point at the offending character.
+impl FromStr for SnailNumber {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match SnailNumber::parse_prefix(s) {
+            Ok((n, "")) => Ok(n),
+            Ok((_, rest)) => Err(ParseError::at(s, rest, "end of line")),
+            Err(rest) => {
+                let c = &rest[..rest.chars().next().map_or(0, char::len_utf8)];
+                Err(ParseError::at(s, c, "a snailfish number"))
+            }
+        }
     }
 }
 
 impl Debug for SnailNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for SnailNumber {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Branch(arg0, arg1) => f.debug_list().entry(arg0).entry(arg1).finish(),
-            Self::Value(arg0) => write!(f, "{}", arg0),
+            Branch(left, right) => write!(f, "[{},{}]", left, right),
+            Value(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    const EXAMPLE: &str = "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]
+[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]
+[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]
+[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]
+[7,[5,[[3,8],[1,4]]]]
+[[2,[2,2]],[8,[8,1]]]
+[2,9]
+[1,[[[9,3],9],[[9,0],[0,7]]]]
+[[[5,[7,4]],7],1]
+[[[[4,2],2],6],[8,7]]";
+
+    fn parse(s: &str) -> SnailNumber {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(3488));
+        assert_eq!(solve_part2(&input), Ok(3805));
+    }
+
+    #[test]
+    fn example_flat() {
+        let input = input_generator_flat1(EXAMPLE).unwrap();
+        assert_eq!(solve_part1_flat(&input), Ok(3488));
+        assert_eq!(solve_part2_flat(&input), Ok(3805));
+    }
+
+    #[test]
+    fn largest_magnitude_of_equal_lines() {
+        let input = input_generator("[9,9]\n[1,1]\n[9,9]").unwrap();
+        assert_eq!(solve_part2(&input), Ok(225));
+        assert_eq!(solve_part2(&input[..1]), Err(SolveError::EmptyInput));
+    }
+
+    #[test]
+    fn explode() {
+        for (before, after) in [
+            ("[[[[[9,8],1],2],3],4]", "[[[[0,9],2],3],4]"),
+            ("[7,[6,[5,[4,[3,2]]]]]", "[7,[6,[5,[7,0]]]]"),
+            ("[[6,[5,[4,[3,2]]]],1]", "[[6,[5,[7,0]]],3]"),
+            (
+                "[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]",
+                "[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]",
+            ),
+        ] {
+            let mut n = parse(before);
+            assert!(n.explode().unwrap().is_some());
+            assert_eq!(n, parse(after));
+        }
+    }
+
+    #[test]
+    fn add() {
+        let sum = &parse("[[[[4,3],4],4],[7,[[8,4],9]]]") + &parse("[1,1]");
+        assert_eq!(sum, parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
+    }
+
+    #[test]
+    fn trace_reduction() {
+        use Side::*;
+
+        let mut n = Branch(
+            Box::new(parse("[[[[4,3],4],4],[7,[[8,4],9]]]")),
+            Box::new(parse("[1,1]")),
+        );
+        let mut steps = vec![];
+        n.reduce_with(&mut |step, n| steps.push((step.clone(), n.to_string())))
+            .unwrap();
+
+        let explode = |path: &[Side], left, right| ReductionStep::Explode {
+            path: path.to_vec(),
+            left,
+            right,
+        };
+        let split = |path: &[Side], value| ReductionStep::Split {
+            path: path.to_vec(),
+            value,
+        };
+        let expected = [
+            (
+                explode(&[Left, Left, Left, Left], 4, 3),
+                "[[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+            ),
+            (
+                explode(&[Left, Right, Right, Left], 8, 4),
+                "[[[[0,7],4],[15,[0,13]]],[1,1]]",
+            ),
+            (
+                split(&[Left, Right, Left], 15),
+                "[[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+            ),
+            (
+                split(&[Left, Right, Right, Right], 13),
+                "[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+            ),
+            (
+                explode(&[Left, Right, Right, Right], 6, 7),
+                "[[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+            ),
+        ];
+
+        assert_eq!(steps.len(), expected.len());
+        for ((step, n), (expected_step, expected_n)) in steps.iter().zip(expected.iter()) {
+            assert_eq!(step, expected_step);
+            assert_eq!(n, expected_n);
+        }
+        assert_eq!(steps[2].0.to_string(), "split 15 at LRL");
+    }
+
+    #[test]
+    fn magnitude() {
+        assert_eq!(parse("[[1,2],[[3,4],5]]").value(), Ok(143));
+        assert_eq!(
+            parse("[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]").value(),
+            Ok(4140)
+        );
+    }
+
+    #[test]
+    fn sum() {
+        let input = input_generator("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]\n[6,6]").unwrap();
+        let sum = input[1..].iter().fold(input[0].clone(), |a, b| &a + b);
+        assert_eq!(sum, parse("[[[[5,0],[7,4]],[5,5]],[6,6]]"));
+    }
+
+    #[test]
+    fn overflow() {
+        let n = parse("[4294967295,0]");
+        assert_eq!(n.value(), Err(SolveError::Overflow));
+        assert_eq!(
+            FlatSnailNumber::try_from(&n).unwrap().value(),
+            Err(SolveError::Overflow)
+        );
+
+        // The explosion carries the large number over to the 1
+        let s = "[0,0]\n[[[[0,4294967295],1],0],0]";
+        let input = input_generator(s).unwrap();
+        assert_eq!(solve_part1(&input), Err(SolveError::Overflow));
+        assert_eq!(solve_part2(&input), Err(SolveError::Overflow));
+        let input = input_generator_flat1(s).unwrap();
+        assert_eq!(solve_part1_flat(&input), Err(SolveError::Overflow));
+        assert_eq!(solve_part2_flat(&input), Err(SolveError::Overflow));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let nested = |depth| format!("{}1{}", "[".repeat(depth), ",1]".repeat(depth));
+
+        let n = nested(255).parse::<FlatSnailNumber>().unwrap();
+        assert_eq!(n.to_string(), nested(255));
+        assert_eq!(n.checked_add(&n), Err(SolveError::Overflow));
+
+        let err = nested(256).parse::<FlatSnailNumber>().unwrap_err();
+        assert_eq!((err.column, err.text.as_str()), (256, "["));
+        assert!(nested(256).parse::<SnailNumber>().is_ok());
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(solve_part1(&[]), Err(SolveError::EmptyInput));
+    }
+
+    /// Minimal xorshift generator, so the property tests are reproducible without extra
+    /// dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u32) -> u32 {
+            (self.next() % n as u64) as u32
+        }
+
+        /// A random reduced snail number, like the ones in the puzzle input
+        fn snail_number(&mut self, depth: usize) -> SnailNumber {
+            if depth > 0 && (depth == 4 || self.below(3) == 0) {
+                Value(self.below(10))
+            } else {
+                Branch(
+                    Box::new(self.snail_number(depth + 1)),
+                    Box::new(self.snail_number(depth + 1)),
+                )
+            }
+        }
+    }
+
+    fn depth(n: &SnailNumber) -> usize {
+        match n {
+            Branch(l, r) => 1 + depth(l).max(depth(r)),
+            Value(_) => 0,
+        }
+    }
+
+    fn max_leaf(n: &SnailNumber) -> u32 {
+        match n {
+            Branch(l, r) => max_leaf(l).max(max_leaf(r)),
+            Value(v) => *v,
+        }
+    }
+
+    #[test]
+    fn sum_is_reduced() {
+        let mut rng = Rng(0x5eed_1234_abcd_0042);
+        for _ in 0..500 {
+            let l = rng.snail_number(0);
+            let r = rng.snail_number(0);
+            let sum = &l + &r;
+            assert!(depth(&sum) <= 4, "{} + {} = {}", l, r, sum);
+            assert!(max_leaf(&sum) < 10, "{} + {} = {}", l, r, sum);
+        }
+    }
+
+    #[test]
+    fn value_is_deterministic() {
+        let mut rng = Rng(0xdead_beef_cafe_f00d);
+        for _ in 0..500 {
+            let l = rng.snail_number(0);
+            let r = rng.snail_number(0);
+            assert_eq!(l.value(), l.clone().value());
+            assert_eq!((&l + &r).value(), (&l.clone() + &r.clone()).value());
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let mut rng = Rng(0x0123_4567_89ab_cdef);
+        for _ in 0..500 {
+            let n = rng.snail_number(0);
+            assert_eq!(n.to_string().parse(), Ok(n));
+        }
+    }
+
+    #[test]
+    fn flat_matches_tree() {
+        let mut rng = Rng(0x0bad_5eed_0000_0001);
+        for _ in 0..500 {
+            let l = rng.snail_number(0);
+            let r = rng.snail_number(0);
+            let flat_l = FlatSnailNumber::try_from(&l).unwrap();
+            let flat_r = FlatSnailNumber::try_from(&r).unwrap();
+            assert_eq!(flat_l.to_string(), l.to_string());
+
+            let sum = &l + &r;
+            let flat_sum = &flat_l + &flat_r;
+            assert_eq!(flat_sum.to_string(), sum.to_string());
+            assert_eq!(flat_sum.value(), sum.value());
+        }
+    }
+
+    #[test]
+    fn round_trips_unreduced_numbers() {
+        for s in ["0", "123", "[10,[[[[1,2],3],4],5]]", "[[4294967295,0],1]"] {
+            assert_eq!(parse(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn reports_position() {
+        for (s, column, text) in [
+            ("", 1, ""),
+            ("[1,2", 5, ""),
+            ("[1;2]", 3, ";"),
+            ("[1,[2,x]]", 7, "x"),
+            ("[1,2]]", 6, "]"),
+            ("[4294967296,1]", 2, "4"),
+        ] {
+            let err = s.parse::<SnailNumber>().unwrap_err();
+            assert_eq!(
+                (err.line, err.column, err.text.as_str()),
+                (1, column, text),
+                "{}",
+                s
+            );
         }
     }
 }
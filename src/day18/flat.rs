@@ -0,0 +1,234 @@
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
+use std::ops::Add;
+use std::str::FromStr;
+
+use crate::error::{ParseError, SolveError};
+
+use super::{add_to, magnitude, SnailNumber};
+
+/// A regular number together with the number of pairs it is nested in
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Leaf {
+    value: u32,
+    depth: u8,
+}
+
+/// One level deeper, failing with `SolveError::Overflow` once the depth no longer fits
+fn deeper(depth: u8) -> Result<u8, SolveError> {
+    depth.checked_add(1).ok_or(SolveError::Overflow)
+}
+
+/// Snailfish number stored as its regular numbers from left to right, each tagged with its
+/// depth. The tree structure is implied by the depths: the first two adjacent leaves with the
+/// same depth are always siblings. This avoids all the boxing of `SnailNumber` and turns
+/// exploding and splitting into linear scans over a small vector.
+#[derive(Clone, PartialEq, Eq)]
+pub struct FlatSnailNumber {
+    leaves: Vec<Leaf>,
+}
+
+impl FlatSnailNumber {
+    pub fn value(&self) -> Result<u32, SolveError> {
+        let mut stack: Vec<Leaf> = Vec::with_capacity(self.leaves.len());
+        for &leaf in self.leaves.iter() {
+            let mut leaf = leaf;
+            while let Some(&top) = stack.last() {
+                if top.depth != leaf.depth {
+                    break;
+                }
+                stack.pop();
+                leaf = Leaf {
+                    value: magnitude(top.value, leaf.value)?,
+                    depth: leaf.depth - 1,
+                };
+            }
+            stack.push(leaf);
+        }
+
+        Ok(stack.first().map_or(0, |leaf| leaf.value))
+    }
+
+    pub fn reduce(&mut self) -> Result<(), SolveError> {
+        // Nothing left of these positions can explode or split, respectively. An explosion only
+        // changes its left neighbour and a split only the pair it creates, so the scans never
+        // have to start over from the beginning.
+        let mut explode_from = 0;
+        let mut split_from = 0;
+
+        loop {
+            if let Some(i) = self.explode(explode_from)? {
+                explode_from = i.saturating_sub(1);
+                split_from = split_from.min(explode_from);
+            } else if let Some(i) = self.split(split_from)? {
+                explode_from = i.saturating_sub(1);
+                split_from = i;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// The reduced sum of `self` and `rhs`
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, SolveError> {
+        let mut leaves = Vec::with_capacity(self.leaves.len() + rhs.leaves.len());
+        for leaf in self.leaves.iter().chain(rhs.leaves.iter()) {
+            leaves.push(Leaf {
+                value: leaf.value,
+                depth: deeper(leaf.depth)?,
+            });
+        }
+
+        let mut res = FlatSnailNumber { leaves };
+        res.reduce()?;
+        Ok(res)
+    }
+
+    /// Explodes the leftmost pair nested inside four pairs, starting the search at `from`, and
+    /// returns the position of the resulting 0
+    fn explode(&mut self, from: usize) -> Result<Option<usize>, SolveError> {
+        let i = match self.leaves[from..]
+            .windows(2)
+            .position(|w| w[0].depth > 4 && w[0].depth == w[1].depth)
+        {
+            Some(i) => from + i,
+            None => return Ok(None),
+        };
+
+        let Leaf { value: left, depth } = self.leaves[i];
+        let right = self.leaves[i + 1].value;
+
+        if i > 0 {
+            add_to(&mut self.leaves[i - 1].value, left)?;
+        }
+        if let Some(next) = self.leaves.get_mut(i + 2) {
+            add_to(&mut next.value, right)?;
+        }
+
+        self.leaves[i] = Leaf {
+            value: 0,
+            depth: depth - 1,
+        };
+        self.leaves.remove(i + 1);
+        Ok(Some(i))
+    }
+
+    /// Splits the leftmost regular number of at least 10, starting the search at `from`, and
+    /// returns its position
+    fn split(&mut self, from: usize) -> Result<Option<usize>, SolveError> {
+        let i = match self.leaves[from..].iter().position(|leaf| leaf.value >= 10) {
+            Some(i) => from + i,
+            None => return Ok(None),
+        };
+
+        let Leaf { value, depth } = self.leaves[i];
+        let depth = deeper(depth)?;
+        self.leaves[i] = Leaf {
+            value: value / 2,
+            depth,
+        };
+        self.leaves.insert(
+            i + 1,
+            Leaf {
+                value: value.div_ceil(2),
+                depth,
+            },
+        );
+        Ok(Some(i))
+    }
+
+    /// Writes the leaves starting at `*pos` that form a subtree at `depth`
+    fn fmt_subtree(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        pos: &mut usize,
+        depth: u8,
+    ) -> std::fmt::Result {
+        let leaf = self.leaves[*pos];
+        if leaf.depth == depth {
+            *pos += 1;
+            write!(f, "{}", leaf.value)
+        } else {
+            write!(f, "[")?;
+            self.fmt_subtree(f, pos, depth + 1)?;
+            write!(f, ",")?;
+            self.fmt_subtree(f, pos, depth + 1)?;
+            write!(f, "]")
+        }
+    }
+
+    fn flatten(number: &SnailNumber, depth: u8, leaves: &mut Vec<Leaf>) -> Result<(), SolveError> {
+        match number {
+            SnailNumber::Branch(left, right) => {
+                Self::flatten(left, deeper(depth)?, leaves)?;
+                Self::flatten(right, deeper(depth)?, leaves)
+            }
+            SnailNumber::Value(value) => {
+                leaves.push(Leaf {
+                    value: *value,
+                    depth,
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Fails with `SolveError::Overflow` for numbers nested more than 255 pairs deep
+impl TryFrom<&SnailNumber> for FlatSnailNumber {
+    type Error = SolveError;
+
+    fn try_from(number: &SnailNumber) -> Result<Self, Self::Error> {
+        let mut leaves = vec![];
+        Self::flatten(number, 0, &mut leaves)?;
+        Ok(FlatSnailNumber { leaves })
+    }
+}
+
+impl FromStr for FlatSnailNumber {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number: SnailNumber = s.parse()?;
+        FlatSnailNumber::try_from(&number).map_err(|_| {
+            // Points at the first pair that is nested too deep
+            let mut depth = 0usize;
+            let pos = s
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '[' => depth += 1,
+                        ']' => depth -= 1,
+                        _ => {}
+                    }
+                    depth > u8::MAX as usize
+                })
+                .map_or(0, |(pos, _)| pos);
+            ParseError::at(s, &s[pos..pos + 1], "at most 255 nested pairs")
+        })
+    }
+}
+
+/// The reduced sum, for numbers that are known to be reduced already
+impl Add for &FlatSnailNumber {
+    type Output = FlatSnailNumber;
+
+    /// # Panics
+    ///
+    /// If a regular number overflows while reducing, see `checked_add`
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect("regular number overflowed")
+    }
+}
+
+impl Display for FlatSnailNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_subtree(f, &mut 0, 0)
+    }
+}
+
+impl Debug for FlatSnailNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
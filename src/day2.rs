@@ -1,94 +1,189 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::str::FromStr;
 
-#[derive(Debug)]
-enum Direction {
+use crate::error::{ParseError, SolveError};
+
+pub mod plot;
+mod png;
+mod script;
+mod submarine;
+pub use submarine::{Aimed, Direct, Models, State, SteeringModel, Submarine};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
     Forward,
+    Back,
     Up,
     Down,
+    /// Straight down by the value, whatever the aim
+    Dive,
+    /// Straight up to the surface, takes no value
+    Surface,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Command {
-    direction: Direction,
-    value: i32,
+    pub direction: Direction,
+    /// Can be negative, 0 for `surface`
+    pub value: i64,
 }
 
-type Position = (i32, i32);
-
 impl Command {
-    fn apply1(&self, pos: &mut Position) {
-        match self.direction {
-            Direction::Up => pos.1 -= self.value,
-            Direction::Down => pos.1 += self.value,
-            Direction::Forward => pos.0 += self.value,
-        }
-    }
-
-    fn apply2(&self, state: &mut State) {
-        match self.direction {
-            Direction::Up => state.aim -= self.value,
-            Direction::Down => state.aim += self.value,
-            Direction::Forward => {
-                state.position.0 += self.value;
-                state.position.1 += self.value * state.aim;
-            }
-        }
-    }
-}
-
-impl FromStr for Command {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (direction, value) = s.split_once(" ").ok_or(())?;
-
-        let direction = match direction {
+    /// Expected instead of an unknown verb in a single command
+    const VERBS: &'static str = "one of `forward`, `back`, `up`, `down`, `dive` or `surface`";
+
+    /// Parses a single command from `text`, which has to be a subslice of `line` so that errors
+    /// point at the right column. `verbs` describes what's allowed instead of an unknown verb.
+    fn parse_in(line: &str, text: &str, verbs: &'static str) -> Result<Self, ParseError> {
+        let mut tokens = text.split_whitespace();
+        let verb = tokens
+            .next()
+            .ok_or_else(|| ParseError::at(line, text, "a command like `forward 5`"))?;
+
+        let direction = match verb {
             "forward" => Direction::Forward,
+            "back" => Direction::Back,
             "up" => Direction::Up,
             "down" => Direction::Down,
+            "dive" => Direction::Dive,
+            "surface" => Direction::Surface,
             _ => {
-                return Err(());
+                return Err(ParseError::at(line, verb, verbs));
             }
         };
 
-        let value = value.parse().map_err(|_| ())?;
+        let value = if direction == Direction::Surface {
+            0
+        } else {
+            let value = tokens
+                .next()
+                .ok_or_else(|| ParseError::at(line, &text[text.len()..], "an integer"))?;
+            value
+                .parse()
+                .map_err(|_| ParseError::at(line, value, "an integer"))?
+        };
+
+        if let Some(extra) = tokens.next() {
+            return Err(ParseError::at(line, extra, "the end of the command"));
+        }
 
         Ok(Command { direction, value })
     }
 }
 
+impl FromStr for Command {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Command::parse_in(s, s, Command::VERBS)
+    }
+}
+
+/// Parses a dive plan, see `script::parse` for the syntax. The puzzle input is a plan without
+/// any of the extensions.
 #[aoc_generator(day2)]
-pub fn input_generator(s: &str) -> Vec<Command> {
-    s.lines().filter_map(|n| n.parse().ok()).collect()
+pub fn input_generator(s: &str) -> Result<Vec<Command>, ParseError> {
+    script::parse(s)
 }
 
 #[aoc(day2, part1)]
-pub fn solve_part1(input: &[Command]) -> u32 {
-    let mut pos = Position::default();
-    for cmd in input {
-        // let old_pos = pos;
-        cmd.apply1(&mut pos);
-        // println!("{:?}: {:?} => {:?}", cmd, old_pos, pos);
-    }
+pub fn solve_part1(input: &[Command]) -> Result<i128, SolveError> {
+    final_product(&Direct, input, false)
+}
 
-    (pos.0 * pos.1) as u32
+#[aoc(day2, part2)]
+pub fn solve_part2(input: &[Command]) -> Result<i128, SolveError> {
+    final_product(&Aimed, input, false)
 }
 
-#[derive(Debug, Default)]
-struct State {
-    aim: i32,
-    position: Position,
+#[aoc(day2, part1, NoFlying)]
+pub fn solve_part1_no_flying(input: &[Command]) -> Result<i128, SolveError> {
+    final_product(&Direct, input, true)
 }
 
-#[aoc(day2, part2)]
-pub fn solve_part2(input: &[Command]) -> u32 {
-    let mut state = State::default();
-    for cmd in input {
-        // let old_pos = pos;
-        cmd.apply2(&mut state);
-        // println!("{:?}: {:?} => {:?}", cmd, old_pos, pos);
+#[aoc(day2, part2, NoFlying)]
+pub fn solve_part2_no_flying(input: &[Command]) -> Result<i128, SolveError> {
+    final_product(&Aimed, input, true)
+}
+
+/// Product of the horizontal position and the depth the commands lead to. Both fit into an
+/// `i64`, so their product can't overflow an `i128`. With `no_flying`, going above the surface
+/// fails with `SolveError::AboveSurface`.
+pub fn final_product(
+    model: &dyn SteeringModel,
+    commands: &[Command],
+    no_flying: bool,
+) -> Result<i128, SolveError> {
+    let mut submarine = Submarine::new(model).no_flying(no_flying);
+    submarine.run(commands)?;
+    let state = submarine.state();
+    Ok(state.horizontal as i128 * state.depth as i128)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) const EXAMPLE: &str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(150));
+        assert_eq!(solve_part2(&input), Ok(900));
     }
 
-    (state.position.0 * state.position.1) as u32
+    #[test]
+    fn large_and_negative() {
+        let input = input_generator("forward 3000000000\ndown 3000000000").unwrap();
+        assert_eq!(solve_part1(&input), Ok(9_000_000_000_000_000_000));
+
+        let input = input_generator("forward 5\nup 3\nforward 2").unwrap();
+        assert_eq!(solve_part1(&input), Ok(-21));
+        assert_eq!(solve_part2(&input), Ok(-42));
+
+        let input = input_generator(&format!("down {}\nforward 2", i64::MAX)).unwrap();
+        assert_eq!(solve_part2(&input), Err(SolveError::Overflow));
+    }
+
+    #[test]
+    fn no_flying() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1_no_flying(&input), Ok(150));
+        assert_eq!(solve_part2_no_flying(&input), Ok(900));
+
+        // Rises to -3 directly, and to -6 along the aim on the last command
+        let input = input_generator("forward 5\nup 3\nforward 2").unwrap();
+        let above = |command, depth| Err(SolveError::AboveSurface { command, depth });
+        assert_eq!(solve_part1_no_flying(&input), above(2, -3));
+        assert_eq!(solve_part2_no_flying(&input), above(3, -6));
+        assert_eq!(final_product(&Aimed, &input, false), Ok(-42));
+    }
+
+    #[test]
+    fn reports_bad_line() {
+        let err = input_generator("forward 5\nbackward 3").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert_eq!(err.text, "backward");
+
+        let err = input_generator("forward 5\nup three").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 4));
+
+        let err = input_generator("forward 5\n  down\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 7, ""));
+
+        let err = input_generator("surface 3").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (1, 9, "3"));
+
+        // Blocks are only part of plans
+        let err = "repeat 2 {".parse::<Command>().unwrap_err();
+        assert!(!err.expected.contains("repeat"));
+        let err = input_generator("sideways 2").unwrap_err();
+        assert!(err.expected.contains("repeat"));
+    }
 }
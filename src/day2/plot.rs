@@ -0,0 +1,452 @@
+//! Plots submarine trajectories as horizontal position against depth, with depth growing
+//! downwards like on a dive chart. The same drawing is rendered as SVG or as a PNG image.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+use super::{png, State};
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 500;
+/// Room for the tick labels and axis titles around the plot area
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 20.0;
+const TOP: f64 = 20.0;
+const BOTTOM: f64 = 50.0;
+/// At most this many points of a path get their command index written next to them
+const MAX_LABELS: usize = 20;
+
+type Colour = [u8; 3];
+
+const BLACK: Colour = [0, 0, 0];
+const GREY: Colour = [160, 160, 160];
+const LIGHT_GREY: Colour = [230, 230, 230];
+const WHITE: Colour = [255, 255, 255];
+/// One colour per path, reused if there are more paths
+const COLOURS: [Colour; 5] = [
+    [31, 119, 180],
+    [214, 39, 40],
+    [44, 160, 44],
+    [148, 103, 189],
+    [255, 127, 14],
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// The drawing primitives the plot is made of
+trait Canvas {
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), colour: Colour, dashed: bool);
+    fn dot(&mut self, at: (f64, f64), colour: Colour);
+    fn text(&mut self, at: (f64, f64), text: &str, anchor: Anchor, colour: Colour);
+    /// Axis title along the left edge, which the SVG rotates
+    fn vertical_text(&mut self, at: (f64, f64), text: &str);
+}
+
+/// Writes the trajectories, each with its name for the legend, as an SVG image
+pub fn svg(paths: &[(&str, &[State])], out: &mut impl Write) -> io::Result<()> {
+    let mut svg = Svg(String::new());
+    draw(paths, &mut svg);
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="11">"#,
+        w = WIDTH,
+        h = HEIGHT
+    )?;
+    writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#)?;
+    out.write_all(svg.0.as_bytes())?;
+    writeln!(out, "</svg>")
+}
+
+/// Same as `svg`, but as a PNG image. Its built-in font only has digits and `-`, so the
+/// numbers are there but names and axis titles aren't.
+pub fn png(paths: &[(&str, &[State])], out: &mut impl Write) -> io::Result<()> {
+    let mut raster = Raster {
+        pixels: vec![WHITE; (WIDTH * HEIGHT) as usize],
+    };
+    draw(paths, &mut raster);
+
+    let data = raster.pixels.concat();
+    png::write_rgb(WIDTH, HEIGHT, &data, out)
+}
+
+/// Maps positions and depths onto the plot area
+struct Frame {
+    horizontal: (i64, i64),
+    depth: (i64, i64),
+}
+
+impl Frame {
+    /// Smallest frame that contains all states and the starting point
+    fn around(paths: &[(&str, &[State])]) -> Self {
+        let states = paths.iter().flat_map(|(_, states)| states.iter());
+        let (mut horizontal, mut depth) = ((0, 0), (0, 0));
+        for state in states {
+            horizontal = (
+                horizontal.0.min(state.horizontal),
+                horizontal.1.max(state.horizontal),
+            );
+            depth = (depth.0.min(state.depth), depth.1.max(state.depth));
+        }
+        Frame { horizontal, depth }
+    }
+
+    fn x(&self, horizontal: i64) -> f64 {
+        LEFT + scale(horizontal, self.horizontal) * (WIDTH as f64 - LEFT - RIGHT)
+    }
+
+    fn y(&self, depth: i64) -> f64 {
+        TOP + scale(depth, self.depth) * (HEIGHT as f64 - TOP - BOTTOM)
+    }
+
+    fn project(&self, state: &State) -> (f64, f64) {
+        (self.x(state.horizontal), self.y(state.depth))
+    }
+}
+
+/// Position of `value` within `range`, from 0 to 1
+fn scale(value: i64, (min, max): (i64, i64)) -> f64 {
+    if min == max {
+        0.5
+    } else {
+        (value as f64 - min as f64) / (max as f64 - min as f64)
+    }
+}
+
+/// Round tick values covering `range`, about 6 of them
+fn ticks((min, max): (i64, i64)) -> Vec<i64> {
+    // Wide enough that extreme ranges can't overflow
+    let (min, max) = (min as i128, max as i128);
+    let span = (max - min).max(1) as f64;
+    let magnitude = 10f64.powi((span / 6.0).log10().floor() as i32);
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&step| span / step <= 6.0)
+        .unwrap_or(10.0 * magnitude)
+        .max(1.0) as i128;
+
+    let first = (min + step - 1).div_euclid(step) * step;
+    (0..)
+        .map(|i| first + i * step)
+        .take_while(|&t| t <= max)
+        .map(|t| t as i64)
+        .collect()
+}
+
+fn draw(paths: &[(&str, &[State])], canvas: &mut impl Canvas) {
+    let frame = Frame::around(paths);
+    let (left, right) = (LEFT, WIDTH as f64 - RIGHT);
+    let (top, bottom) = (TOP, HEIGHT as f64 - BOTTOM);
+
+    for tick in ticks(frame.horizontal) {
+        let x = frame.x(tick);
+        canvas.line((x, top), (x, bottom), LIGHT_GREY, false);
+        canvas.line((x, bottom), (x, bottom + 5.0), BLACK, false);
+        canvas.text((x, bottom + 18.0), &tick.to_string(), Anchor::Middle, BLACK);
+    }
+    for tick in ticks(frame.depth) {
+        let y = frame.y(tick);
+        canvas.line((left, y), (right, y), LIGHT_GREY, false);
+        canvas.line((left - 5.0, y), (left, y), BLACK, false);
+        canvas.text((left - 8.0, y + 4.0), &tick.to_string(), Anchor::End, BLACK);
+    }
+
+    let surface = frame.y(0);
+    canvas.line((left, surface), (right, surface), GREY, true);
+    canvas.line((left, top), (left, bottom), BLACK, false);
+    canvas.line((left, bottom), (right, bottom), BLACK, false);
+    canvas.text(
+        ((left + right) / 2.0, HEIGHT as f64 - 10.0),
+        "horizontal position",
+        Anchor::Middle,
+        BLACK,
+    );
+    canvas.vertical_text((20.0, (top + bottom) / 2.0), "depth");
+
+    for (i, (_, states)) in paths.iter().enumerate() {
+        let colour = COLOURS[i % COLOURS.len()];
+        let points = states.iter().map(|s| frame.project(s)).collect::<Vec<_>>();
+        for pair in points.windows(2) {
+            canvas.line(pair[0], pair[1], colour, false);
+        }
+
+        // Index 0 is the starting point, index n the state after the n-th command. Commands
+        // that don't move the submarine share a label, like `1-2`.
+        let every = points.len().div_ceil(MAX_LABELS).max(1);
+        let mut start = 0;
+        for (index, &(x, y)) in points.iter().enumerate() {
+            canvas.dot((x, y), colour);
+            if points.get(index + 1) == Some(&(x, y)) {
+                continue;
+            }
+
+            let last = index + 1 == points.len();
+            if last || (start..=index).any(|i| i % every == 0) {
+                let label = if start == index {
+                    index.to_string()
+                } else {
+                    format!("{}-{}", start, index)
+                };
+                canvas.text((x + 4.0, y - 4.0), &label, Anchor::Start, colour);
+            }
+            start = index + 1;
+        }
+    }
+
+    // The legend goes below the plot, where it can't hide any of the paths
+    for (i, (name, _)) in paths.iter().enumerate() {
+        let colour = COLOURS[i % COLOURS.len()];
+        let (x, y) = (10.0 + 90.0 * i as f64, HEIGHT as f64 - 10.0);
+        canvas.line((x, y - 4.0), (x + 20.0, y - 4.0), colour, false);
+        canvas.text((x + 25.0, y), name, Anchor::Start, BLACK);
+    }
+}
+
+struct Svg(String);
+
+fn rgb([r, g, b]: Colour) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Escapes the characters that are special in SVG text
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl Canvas for Svg {
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), colour: Colour, dashed: bool) {
+        let dash = if dashed {
+            r#" stroke-dasharray="4 3""#
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            self.0,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}"{}/>"#,
+            from.0,
+            from.1,
+            to.0,
+            to.1,
+            rgb(colour),
+            dash
+        );
+    }
+
+    fn dot(&mut self, at: (f64, f64), colour: Colour) {
+        let _ = writeln!(
+            self.0,
+            r#"<circle cx="{:.1}" cy="{:.1}" r="2.5" fill="{}"/>"#,
+            at.0,
+            at.1,
+            rgb(colour)
+        );
+    }
+
+    fn text(&mut self, at: (f64, f64), text: &str, anchor: Anchor, colour: Colour) {
+        let anchor = match anchor {
+            Anchor::Start => "start",
+            Anchor::Middle => "middle",
+            Anchor::End => "end",
+        };
+        let _ = writeln!(
+            self.0,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="{}" fill="{}">{}</text>"#,
+            at.0,
+            at.1,
+            anchor,
+            rgb(colour),
+            escape(text)
+        );
+    }
+
+    fn vertical_text(&mut self, at: (f64, f64), text: &str) {
+        let _ = writeln!(
+            self.0,
+            r#"<text transform="translate({:.1} {:.1}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            at.0,
+            at.1,
+            escape(text)
+        );
+    }
+}
+
+/// 3x5 pixel glyphs for `0` to `9` and `-`, one row per entry with the leftmost pixel in the
+/// highest of the 3 bits
+const DIGITS: [[u8; 5]; 11] = [
+    [7, 5, 5, 5, 7],
+    [2, 6, 2, 2, 7],
+    [7, 1, 7, 4, 7],
+    [7, 1, 3, 1, 7],
+    [5, 5, 7, 1, 1],
+    [7, 4, 7, 1, 7],
+    [7, 4, 7, 5, 7],
+    [7, 1, 1, 2, 2],
+    [7, 5, 7, 5, 7],
+    [7, 5, 7, 1, 7],
+    [0, 0, 7, 0, 0],
+];
+/// Size of a glyph pixel in image pixels
+const GLYPH_SCALE: i64 = 2;
+const GLYPH_ADVANCE: i64 = 4 * GLYPH_SCALE;
+
+struct Raster {
+    pixels: Vec<Colour>,
+}
+
+impl Raster {
+    fn set(&mut self, x: i64, y: i64, colour: Colour) {
+        if (0..WIDTH as i64).contains(&x) && (0..HEIGHT as i64).contains(&y) {
+            self.pixels[(y * WIDTH as i64 + x) as usize] = colour;
+        }
+    }
+}
+
+impl Canvas for Raster {
+    /// Bresenham's line algorithm, dashes are runs of 4 pixels with gaps of 3
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), colour: Colour, dashed: bool) {
+        let (mut x, mut y) = (from.0.round() as i64, from.1.round() as i64);
+        let (x1, y1) = (to.0.round() as i64, to.1.round() as i64);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut err = dx + dy;
+
+        for i in 0.. {
+            if !dashed || i % 7 < 4 {
+                self.set(x, y, colour);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn dot(&mut self, at: (f64, f64), colour: Colour) {
+        let (x, y) = (at.0.round() as i64, at.1.round() as i64);
+        for dy in -2..=2 {
+            for dx in -2..=2 {
+                if dx * dx + dy * dy <= 5 {
+                    self.set(x + dx, y + dy, colour);
+                }
+            }
+        }
+    }
+
+    /// Only digits and `-` are drawn, other characters are left as blanks
+    fn text(&mut self, at: (f64, f64), text: &str, anchor: Anchor, colour: Colour) {
+        let width = text.chars().count() as i64 * GLYPH_ADVANCE - GLYPH_SCALE;
+        let left = at.0.round() as i64
+            - match anchor {
+                Anchor::Start => 0,
+                Anchor::Middle => width / 2,
+                Anchor::End => width,
+            };
+        let top = at.1.round() as i64 - 5 * GLYPH_SCALE;
+
+        for (i, c) in text.chars().enumerate() {
+            let glyph = match c {
+                '0'..='9' => DIGITS[c as usize - '0' as usize],
+                '-' => DIGITS[10],
+                _ => continue,
+            };
+            let x0 = left + i as i64 * GLYPH_ADVANCE;
+            for (row, bits) in glyph.iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (4 >> col) == 0 {
+                        continue;
+                    }
+                    for p in 0..GLYPH_SCALE * GLYPH_SCALE {
+                        let x = x0 + col * GLYPH_SCALE + p % GLYPH_SCALE;
+                        let y = top + row as i64 * GLYPH_SCALE + p / GLYPH_SCALE;
+                        self.set(x, y, colour);
+                    }
+                }
+            }
+        }
+    }
+
+    fn vertical_text(&mut self, _at: (f64, f64), _text: &str) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::{input_generator, tests::EXAMPLE, Models};
+
+    fn with_example_paths(f: impl FnOnce(&[(&str, &[State])])) {
+        let commands = input_generator(EXAMPLE).unwrap();
+        let models = Models::default();
+        let trajectories = models
+            .compare(&commands, false)
+            .into_iter()
+            .map(|(name, trajectory)| (name, trajectory.unwrap()))
+            .collect::<Vec<_>>();
+        let paths = trajectories
+            .iter()
+            .map(|(name, states)| (*name, states.as_slice()))
+            .collect::<Vec<_>>();
+        f(&paths)
+    }
+
+    #[test]
+    fn nice_ticks() {
+        assert_eq!(ticks((0, 15)), vec![0, 5, 10, 15]);
+        assert_eq!(ticks((0, 60)), vec![0, 10, 20, 30, 40, 50, 60]);
+        assert_eq!(ticks((-7, 3)), vec![-6, -4, -2, 0, 2]);
+        assert_eq!(ticks((0, 0)), vec![0]);
+        assert_eq!(ticks((0, 1_000_000)).len(), 6);
+        assert!(ticks((i64::MIN, i64::MAX)).len() <= 7);
+    }
+
+    #[test]
+    fn svg_plot() {
+        let mut out = vec![];
+        with_example_paths(|paths| svg(paths, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("<svg "));
+        assert!(out.trim_end().ends_with("</svg>"));
+        // One dot per state of both paths, each labelled with its command index
+        assert_eq!(out.matches("<circle").count(), 2 * 7);
+        assert_eq!(out.matches(">6</text>").count(), 2);
+        // `down 5` doesn't move the aimed submarine
+        assert_eq!(out.matches(">1-2</text>").count(), 1);
+        assert!(out.contains(">horizontal position</text>"));
+        assert!(out.contains(">aimed</text>"));
+        // The aimed path ends at the bottom right of the plot area
+        assert!(out.contains(r#"<circle cx="780.0" cy="450.0""#));
+    }
+
+    #[test]
+    fn png_plot() {
+        with_example_paths(|paths| {
+            let mut raster = Raster {
+                pixels: vec![WHITE; (WIDTH * HEIGHT) as usize],
+            };
+            draw(paths, &mut raster);
+            let at = |x: u32, y: u32| raster.pixels[(y * WIDTH + x) as usize];
+            assert_eq!(at(780, 450), COLOURS[1]);
+            assert_eq!(at(80, 300), BLACK);
+
+            let mut out = vec![];
+            png(paths, &mut out).unwrap();
+            assert!(out.starts_with(b"\x89PNG"));
+        });
+    }
+}
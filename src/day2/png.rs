@@ -0,0 +1,114 @@
+//! A minimal PNG encoder for RGB images. The image data is stored without compression, which
+//! keeps the encoder tiny at the cost of file size.
+
+use std::io::{self, Write};
+
+/// Largest block of uncompressed data deflate allows
+const MAX_STORED: usize = 65535;
+
+/// Writes `pixels`, which holds `width * height` RGB triples in row-major order
+pub fn write_rgb(width: u32, height: u32, pixels: &[u8], out: &mut impl Write) -> io::Result<()> {
+    assert_eq!(
+        pixels.len(),
+        width as usize * height as usize * 3,
+        "pixel data doesn't match the image size"
+    );
+
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGB, default compression, filtering and no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    chunk(b"IHDR", &header, out)?;
+
+    // Every row starts with its filter type, 0 for none
+    let mut raw = Vec::with_capacity(pixels.len() + height as usize);
+    for row in pixels.chunks(width as usize * 3).filter(|_| width > 0) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    chunk(b"IDAT", &zlib_stored(&raw), out)?;
+    chunk(b"IEND", &[], out)
+}
+
+fn chunk(kind: &[u8; 4], data: &[u8], out: &mut impl Write) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data.iter()).copied());
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// Wraps `data` into a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+
+    let blocks = data.chunks(MAX_STORED).collect::<Vec<_>>();
+    if blocks.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i + 1 == blocks.len();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(bytes: impl Iterator<Item = u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND".iter().copied()), 0xae42_6082);
+        assert_eq!(crc32(b"123456789".iter().copied()), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn layout() {
+        let pixels = vec![0x80; 300 * 300 * 3];
+        let mut out = vec![];
+        write_rgb(300, 300, &pixels, &mut out).unwrap();
+
+        assert!(out.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert_eq!(&out[16..24], &[0, 0, 1, 44, 0, 0, 1, 44]);
+        assert!(out.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        // Stored blocks of at most 64K for the rows, plus zlib and chunk framing
+        let raw: usize = 300 * (300 * 3 + 1);
+        let blocks = raw.div_ceil(MAX_STORED);
+        let idat = 2 + blocks * 5 + raw + 4;
+        assert_eq!(out.len(), 8 + 25 + (12 + idat) + 12);
+    }
+}
@@ -0,0 +1,217 @@
+//! Dive plans, a line-oriented language around the submarine commands:
+//!
+//! ```text
+//! # Comments run to the end of the line
+//! forward 5
+//! repeat 3 {
+//!     dive 2      # straight down, whatever the aim
+//!     back -1     # negative values are fine
+//! }
+//! surface
+//! ```
+//!
+//! Repeat blocks can be nested and are expanded while parsing, so the result is a plain list
+//! of commands.
+
+use crate::error::{numbered_lines, ParseError};
+
+use super::Command;
+
+/// Upper bound on the number of commands after expanding all repeat blocks, to keep nested
+/// blocks from exhausting memory
+const MAX_COMMANDS: usize = 10_000_000;
+
+/// Expected instead of an unknown verb, plans can also open blocks
+const VERBS: &str = "one of `forward`, `back`, `up`, `down`, `dive`, `surface` or `repeat`";
+
+/// A `repeat` block that hasn't been closed yet
+struct Block {
+    count: usize,
+    commands: Vec<Command>,
+    /// Reported if the expanded block is too large
+    too_large: ParseError,
+}
+
+pub fn parse(s: &str) -> Result<Vec<Command>, ParseError> {
+    let mut commands = vec![];
+    let mut blocks: Vec<Block> = vec![];
+    let mut last_line = 0;
+
+    for (line_no, line) in numbered_lines(s) {
+        last_line = line_no;
+        let code = match line.find('#') {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        let text = code.trim();
+        let mut tokens = text.split_whitespace();
+
+        match tokens.next() {
+            None => continue,
+            Some("repeat") => {
+                let block = parse_repeat(line, tokens).map_err(|e| e.on_line(line_no))?;
+                blocks.push(Block {
+                    too_large: block.too_large.on_line(line_no),
+                    ..block
+                });
+            }
+            Some("}") => {
+                if let Some(extra) = tokens.next() {
+                    let err = ParseError::at(line, extra, "the end of the block");
+                    return Err(err.on_line(line_no));
+                }
+                let block = blocks
+                    .pop()
+                    .ok_or_else(|| ParseError::at(line, text, "a command").on_line(line_no))?;
+
+                let parent = blocks.last_mut().map_or(&mut commands, |b| &mut b.commands);
+                let expanded = block.commands.len().saturating_mul(block.count);
+                if parent.len().saturating_add(expanded) > MAX_COMMANDS {
+                    return Err(block.too_large);
+                }
+                parent.extend(block.commands.iter().cycle().take(expanded).cloned());
+            }
+            Some(_) => {
+                let command =
+                    Command::parse_in(line, text, VERBS).map_err(|e| e.on_line(line_no))?;
+                blocks
+                    .last_mut()
+                    .map_or(&mut commands, |b| &mut b.commands)
+                    .push(command);
+            }
+        }
+    }
+
+    if !blocks.is_empty() {
+        return Err(ParseError::new(1, "", "a `}` closing the repeat block").on_line(last_line + 1));
+    }
+    Ok(commands)
+}
+
+/// Parses the rest of a `repeat N {` line, the returned errors are still on line 1
+fn parse_repeat<'a>(
+    line: &'a str,
+    mut tokens: impl Iterator<Item = &'a str>,
+) -> Result<Block, ParseError> {
+    let end = &line[line.len()..];
+
+    let count = tokens
+        .next()
+        .ok_or_else(|| ParseError::at(line, end, "a repeat count"))?;
+    let too_large = ParseError::at(
+        line,
+        count,
+        "a repeat count that keeps the plan below 10 million commands",
+    );
+    let count = count
+        .parse()
+        .map_err(|_| ParseError::at(line, count, "a repeat count"))?;
+
+    match tokens.next() {
+        Some("{") => {}
+        Some(other) => return Err(ParseError::at(line, other, "`{`")),
+        None => return Err(ParseError::at(line, end, "`{`")),
+    }
+    if let Some(extra) = tokens.next() {
+        return Err(ParseError::at(line, extra, "the end of the line"));
+    }
+
+    Ok(Block {
+        count,
+        commands: vec![],
+        too_large,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::{solve_part1, solve_part2, Direction};
+
+    const PLAN: &str = "# The example, compressed
+forward 5
+repeat 2 {      # down 10 and forward 8 overall
+    down 4
+    repeat 2 {
+        forward 2
+    }
+    up -1       # same as down 1
+} # end of the outer block
+forward 2";
+
+    #[test]
+    fn expands_blocks() {
+        let commands = parse(PLAN).unwrap();
+        let directions = commands.iter().map(|c| c.direction).collect::<Vec<_>>();
+        assert_eq!(directions.len(), 1 + 2 * 4 + 1);
+        assert_eq!(
+            directions[..6],
+            [
+                Direction::Forward,
+                Direction::Down,
+                Direction::Forward,
+                Direction::Forward,
+                Direction::Up,
+                Direction::Down
+            ]
+        );
+        assert_eq!(commands[4].value, -1);
+
+        // Ends up in the same place as the example
+        assert_eq!(solve_part1(&commands), Ok(150));
+        assert_eq!(solve_part2(&commands), Ok(15 * 72));
+    }
+
+    #[test]
+    fn other_verbs() {
+        let commands = parse("dive 7\nback 3\nsurface\nrepeat 0 {\n}").unwrap();
+        let parsed = commands
+            .iter()
+            .map(|c| (c.direction, c.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parsed,
+            vec![
+                (Direction::Dive, 7),
+                (Direction::Back, 3),
+                (Direction::Surface, 0)
+            ]
+        );
+
+        let commands = parse("down 2\nforward 4\nback 1\ndive 3").unwrap();
+        assert_eq!(solve_part1(&commands), Ok(3 * 5));
+        assert_eq!(solve_part2(&commands), Ok(3 * 9));
+        let commands = parse("down 2\nforward 4\nsurface\nforward 1").unwrap();
+        assert_eq!(solve_part1(&commands), Ok(0));
+        assert_eq!(solve_part2(&commands), Ok(0));
+    }
+
+    #[test]
+    fn reports_line_numbers() {
+        let err = |s| {
+            let e = parse(s).unwrap_err();
+            (e.line, e.column, e.text)
+        };
+
+        assert_eq!(err("forward 1\nrepeat x {"), (2, 8, "x".to_owned()));
+        assert_eq!(err("repeat 2\n}"), (1, 9, "".to_owned()));
+        assert_eq!(
+            err("repeat 2 {\n  sideways 1\n}"),
+            (2, 3, "sideways".to_owned())
+        );
+        assert_eq!(err("forward 1\n}"), (2, 1, "}".to_owned()));
+        assert_eq!(err("repeat 2 {\nforward 1\n"), (3, 1, "".to_owned()));
+        assert_eq!(
+            err("up 1\nrepeat 100000 {\nrepeat 100000 {\nforward 1\n}\n}"),
+            (2, 8, "100000".to_owned())
+        );
+    }
+
+    #[test]
+    fn empty_blocks() {
+        let commands = parse("repeat 18446744073709551615 {\n}\nforward 1").unwrap();
+        assert_eq!(commands.len(), 1);
+        let commands = parse("repeat 18446744073709551615 {\nrepeat 0 {\nup 1\n}\n}").unwrap();
+        assert!(commands.is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+//! Executes commands under interchangeable steering models, so that different interpretations
+//! of the same command log can be compared
+
+use crate::error::SolveError;
+
+use super::{Command, Direction};
+
+/// Where the submarine is and where it is heading
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct State {
+    pub horizontal: i64,
+    /// Grows downwards, negative values are above the surface
+    pub depth: i64,
+    /// Only used by models that steer with an aim, stays 0 for the others
+    pub aim: i64,
+}
+
+/// An interpretation of the submarine commands
+pub trait SteeringModel {
+    /// Name to register and look up the model by
+    fn name(&self) -> &str;
+
+    /// Updates `state` according to `command`, failing with `SolveError::Overflow` if any
+    /// part of it goes out of range
+    fn apply(&self, command: &Command, state: &mut State) -> Result<(), SolveError>;
+}
+
+fn add(a: i64, b: i64) -> Result<i64, SolveError> {
+    a.checked_add(b).ok_or(SolveError::Overflow)
+}
+
+fn sub(a: i64, b: i64) -> Result<i64, SolveError> {
+    a.checked_sub(b).ok_or(SolveError::Overflow)
+}
+
+fn mul(a: i64, b: i64) -> Result<i64, SolveError> {
+    a.checked_mul(b).ok_or(SolveError::Overflow)
+}
+
+/// Part 1, where `up` and `down` change the depth directly
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Direct;
+
+impl SteeringModel for Direct {
+    fn name(&self) -> &str {
+        "direct"
+    }
+
+    fn apply(&self, command: &Command, state: &mut State) -> Result<(), SolveError> {
+        let value = command.value;
+        match command.direction {
+            Direction::Up => state.depth = sub(state.depth, value)?,
+            Direction::Down | Direction::Dive => state.depth = add(state.depth, value)?,
+            Direction::Forward => state.horizontal = add(state.horizontal, value)?,
+            Direction::Back => state.horizontal = sub(state.horizontal, value)?,
+            Direction::Surface => state.depth = 0,
+        }
+        Ok(())
+    }
+}
+
+/// Part 2, where `up` and `down` change the aim and moving forward or back also changes the
+/// depth along it
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Aimed;
+
+impl SteeringModel for Aimed {
+    fn name(&self) -> &str {
+        "aimed"
+    }
+
+    fn apply(&self, command: &Command, state: &mut State) -> Result<(), SolveError> {
+        let value = command.value;
+        match command.direction {
+            Direction::Up => state.aim = sub(state.aim, value)?,
+            Direction::Down => state.aim = add(state.aim, value)?,
+            Direction::Forward => {
+                state.horizontal = add(state.horizontal, value)?;
+                state.depth = add(state.depth, mul(value, state.aim)?)?;
+            }
+            Direction::Back => {
+                state.horizontal = sub(state.horizontal, value)?;
+                state.depth = sub(state.depth, mul(value, state.aim)?)?;
+            }
+            Direction::Dive => state.depth = add(state.depth, value)?,
+            Direction::Surface => {
+                state.depth = 0;
+                state.aim = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs commands under a steering model, recording the state after each of them
+pub struct Submarine<'m> {
+    model: &'m dyn SteeringModel,
+    /// Whether going above the surface is an error
+    no_flying: bool,
+    /// Starts with the initial state, so it is never empty
+    trajectory: Vec<State>,
+}
+
+impl<'m> Submarine<'m> {
+    pub fn new(model: &'m dyn SteeringModel) -> Self {
+        Submarine {
+            model,
+            no_flying: false,
+            trajectory: vec![State::default()],
+        }
+    }
+
+    /// Enables the rule that submarines don't fly, so that commands taking the depth below 0
+    /// fail with `SolveError::AboveSurface`
+    pub fn no_flying(self, no_flying: bool) -> Self {
+        Submarine { no_flying, ..self }
+    }
+
+    /// Executes a single command. If it fails, the submarine stays where it was.
+    pub fn execute(&mut self, command: &Command) -> Result<(), SolveError> {
+        let mut state = self.state();
+        self.model.apply(command, &mut state)?;
+        if self.no_flying && state.depth < 0 {
+            return Err(SolveError::AboveSurface {
+                command: self.trajectory.len(),
+                depth: state.depth,
+            });
+        }
+
+        self.trajectory.push(state);
+        Ok(())
+    }
+
+    /// Executes commands until the first one that fails
+    pub fn run<'c>(
+        &mut self,
+        commands: impl IntoIterator<Item = &'c Command>,
+    ) -> Result<(), SolveError> {
+        for command in commands {
+            self.execute(command)?;
+        }
+        Ok(())
+    }
+
+    /// Current state, after the last executed command
+    pub fn state(&self) -> State {
+        *self.trajectory.last().unwrap()
+    }
+
+    /// The initial state followed by the one after each executed command
+    pub fn trajectory(&self) -> &[State] {
+        &self.trajectory
+    }
+}
+
+/// The steering models to compare, by name
+pub struct Models {
+    models: Vec<Box<dyn SteeringModel>>,
+}
+
+impl Models {
+    /// The models of both puzzle parts
+    pub fn new() -> Self {
+        let mut models = Models::empty();
+        models.register(Box::new(Direct));
+        models.register(Box::new(Aimed));
+        models
+    }
+
+    /// No models at all, to register only custom ones
+    pub fn empty() -> Self {
+        Models { models: vec![] }
+    }
+
+    /// Adds a model, replacing any earlier one with the same name
+    pub fn register(&mut self, model: Box<dyn SteeringModel>) {
+        self.models.retain(|m| m.name() != model.name());
+        self.models.push(model);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn SteeringModel> {
+        self.models
+            .iter()
+            .find(|m| m.name() == name)
+            .map(|m| m.as_ref())
+    }
+
+    /// All models in the order they were registered
+    pub fn iter(&self) -> impl Iterator<Item = &dyn SteeringModel> {
+        self.models.iter().map(|m| m.as_ref())
+    }
+
+    /// Runs the same commands under every model and returns each one's trajectory, or the
+    /// reason it couldn't be completed. `no_flying` is passed on to `Submarine::no_flying`.
+    pub fn compare<'a>(
+        &'a self,
+        commands: &[Command],
+        no_flying: bool,
+    ) -> Vec<(&'a str, Result<Vec<State>, SolveError>)> {
+        self.iter()
+            .map(|model| {
+                let mut submarine = Submarine::new(model).no_flying(no_flying);
+                let trajectory = submarine.run(commands).map(|_| submarine.trajectory);
+                (model.name(), trajectory)
+            })
+            .collect()
+    }
+}
+
+impl Default for Models {
+    fn default() -> Self {
+        Models::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::{input_generator, tests::EXAMPLE};
+
+    /// Moves diagonally, `up` and `down` only change the vertical direction
+    struct Diagonal;
+
+    impl SteeringModel for Diagonal {
+        fn name(&self) -> &str {
+            "diagonal"
+        }
+
+        fn apply(&self, command: &Command, state: &mut State) -> Result<(), SolveError> {
+            match command.direction {
+                Direction::Up => state.aim = -1,
+                Direction::Down => state.aim = 1,
+                Direction::Forward => {
+                    state.horizontal = add(state.horizontal, command.value)?;
+                    state.depth = add(state.depth, mul(command.value, state.aim)?)?;
+                }
+                _ => return Direct.apply(command, state),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn trajectory() {
+        let commands = input_generator(EXAMPLE).unwrap();
+        let mut submarine = Submarine::new(&Aimed);
+        submarine.run(&commands).unwrap();
+
+        let trajectory = submarine.trajectory();
+        assert_eq!(trajectory.len(), commands.len() + 1);
+        assert_eq!(trajectory[0], State::default());
+        assert_eq!(
+            trajectory[3],
+            State {
+                horizontal: 13,
+                depth: 40,
+                aim: 5
+            }
+        );
+        assert_eq!(
+            (submarine.state().horizontal, submarine.state().depth),
+            (15, 60)
+        );
+    }
+
+    #[test]
+    fn registered_models() {
+        let commands = input_generator(EXAMPLE).unwrap();
+        let mut models = Models::default();
+        models.register(Box::new(Diagonal));
+        assert!(models.get("aimed").is_some());
+        assert!(models.get("sideways").is_none());
+
+        let finals = models
+            .compare(&commands, false)
+            .into_iter()
+            .map(|(name, trajectory)| {
+                let last = *trajectory.unwrap().last().unwrap();
+                (name, last.horizontal, last.depth)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            finals,
+            vec![("direct", 15, 10), ("aimed", 15, 60), ("diagonal", 15, 10)]
+        );
+
+        models.register(Box::new(Diagonal));
+        assert_eq!(models.iter().count(), 3);
+
+        let names = |models: &Models| {
+            models
+                .iter()
+                .map(|m| m.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&Models::new()), ["direct", "aimed"]);
+        assert_eq!(names(&Models::new()), names(&Models::default()));
+        let mut models = Models::empty();
+        assert!(models.compare(&commands, false).is_empty());
+        models.register(Box::new(Diagonal));
+        assert_eq!(names(&models), ["diagonal"]);
+    }
+
+    #[test]
+    fn no_flying() {
+        let commands = input_generator("down 2\nforward 1\nup 3\nforward 3").unwrap();
+
+        let mut submarine = Submarine::new(&Direct).no_flying(true);
+        assert_eq!(
+            submarine.run(&commands),
+            Err(SolveError::AboveSurface {
+                command: 3,
+                depth: -1
+            })
+        );
+        assert_eq!(submarine.trajectory().len(), 3);
+        assert_eq!(submarine.state().depth, 2);
+
+        // Only the aim points upwards, which is fine
+        let mut submarine = Submarine::new(&Aimed).no_flying(true);
+        assert_eq!(submarine.run(&commands[..3]), Ok(()));
+        assert!(submarine.run(&commands[3..]).is_err());
+
+        let mut submarine = Submarine::new(&Direct);
+        assert_eq!(submarine.run(&commands), Ok(()));
+        assert_eq!(submarine.state().depth, -1);
+
+        let models = Models::new();
+        let results = models.compare(&commands, true);
+        assert_eq!(
+            results[0],
+            (
+                "direct",
+                Err(SolveError::AboveSurface {
+                    command: 3,
+                    depth: -1
+                })
+            )
+        );
+        assert!(results[1].1.is_err());
+        assert!(models.compare(&commands, false)[0].1.is_ok());
+    }
+
+    #[test]
+    fn overflow() {
+        let commands = input_generator(&format!("down {}\nforward {}", i64::MAX, 2)).unwrap();
+        let mut submarine = Submarine::new(&Aimed);
+        assert_eq!(submarine.run(&commands), Err(SolveError::Overflow));
+        assert_eq!(submarine.trajectory().len(), 2);
+    }
+}
@@ -1,50 +1,167 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{numbered_lines, ParseError, SolveError};
+
+/// The diagnostic report, one binary number per line. All lines have the same number of columns,
+/// which has to be kept around as leading zeros are significant for the epsilon rate.
+pub struct Report {
+    width: usize,
+    values: Vec<u128>,
+}
+
+impl Report {
+    const MAX_WIDTH: usize = u128::BITS as usize;
+
+    fn count_ones(values: &[u128], bit: usize) -> usize {
+        values.iter().filter(|&&n| (n >> bit) & 1 == 1).count()
+    }
+
+    fn mask(&self) -> u128 {
+        u128::MAX >> (Self::MAX_WIDTH - self.width)
+    }
+}
+
 #[aoc_generator(day3)]
-pub fn input_generator(s: &str) -> Vec<u32> {
-    s.lines()
-        .filter_map(|n| u32::from_str_radix(n, 2).ok())
-        .collect()
+pub fn input_generator(s: &str) -> Result<Report, ParseError> {
+    let width = s.lines().next().map(str::len).unwrap_or(0);
+
+    let values = numbered_lines(s)
+        .map(|(line_no, line)| {
+            if line.is_empty() {
+                Err(ParseError::new(1, "", "a binary number"))
+            } else if let Some((pos, c)) = line.char_indices().find(|&(_, c)| c != '0' && c != '1')
+            {
+                let c = &line[pos..pos + c.len_utf8()];
+                Err(ParseError::new(pos + 1, c, "a binary digit"))
+            } else if line.len() > Report::MAX_WIDTH {
+                let rest = &line[Report::MAX_WIDTH..];
+                Err(ParseError::new(
+                    Report::MAX_WIDTH + 1,
+                    rest,
+                    "at most 128 binary digits",
+                ))
+            } else if line.len() != width {
+                let rest = line.get(width..).unwrap_or("");
+                Err(ParseError::new(
+                    width.min(line.len()) + 1,
+                    rest,
+                    "as many binary digits as on the first line",
+                ))
+            } else {
+                // All digits are binary and fit, so this can't fail
+                Ok(line
+                    .bytes()
+                    .fold(0, |acc, digit| acc << 1 | (digit - b'0') as u128))
+            }
+            .map_err(|e| e.on_line(line_no))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Report { width, values })
 }
 
 #[aoc(day3, part1)]
-pub fn solve_part1(input: &[u32]) -> u32 {
-    let mut count = 0;
-    let mut bit_length = 0;
-    let mut pos_counts = vec![0; 32];
+pub fn solve_part1(input: &Report) -> Result<u128, SolveError> {
+    if input.values.is_empty() {
+        return Err(SolveError::EmptyInput);
+    }
 
-    for n in input {
-        let mut i = 0;
+    let count = input.values.len();
+    let gamma = (0..input.width).rev().fold(0, |acc, bit| {
+        let ones = Report::count_ones(&input.values, bit);
+        (acc << 1) | (ones > count / 2) as u128
+    });
 
-        let mut n = *n;
-        while n != 0 {
-            let bit = n & 1;
-            n >>= 1;
+    let epsilon = input.mask() & !gamma;
+    gamma.checked_mul(epsilon).ok_or(SolveError::Overflow)
+}
 
-            pos_counts[i] += bit;
+#[aoc(day3, part2)]
+pub fn solve_part2(input: &Report) -> Result<u128, SolveError> {
+    if input.values.is_empty() {
+        return Err(SolveError::EmptyInput);
+    }
 
-            i += 1;
+    let oxygen = filter_by_bit_criteria(input, true);
+    let co2 = filter_by_bit_criteria(input, false);
+    oxygen.checked_mul(co2).ok_or(SolveError::Overflow)
+}
+
+fn filter_by_bit_criteria(input: &Report, most_common: bool) -> u128 {
+    let mut candidates = input.values.clone();
+
+    for bit in (0..input.width).rev() {
+        if candidates.len() <= 1 {
+            break;
         }
 
-        bit_length = bit_length.max(i);
-        count += 1;
-    }
+        let ones = Report::count_ones(&candidates, bit);
+        let zeros = candidates.len() - ones;
 
-    println!("{}", count);
-    println!("{:?}", pos_counts);
+        // A column that agrees everywhere must not filter out all candidates, so it is skipped
+        // for both ratings
+        if ones == 0 || zeros == 0 {
+            continue;
+        }
 
-    let gamma: u32 = pos_counts
-        .into_iter()
-        .take(bit_length)
-        .map(|v| v > count / 2)
-        .rev()
-        .fold(0, |acc, b| (acc << 1) | b as u32);
+        let keep_ones = if most_common {
+            ones >= zeros
+        } else {
+            ones < zeros
+        };
+        candidates.retain(|&n| ((n >> bit) & 1 == 1) == keep_ones);
+    }
 
-	let epsilon = (u32::MAX >> 32 - bit_length) & !gamma;
-    gamma * epsilon
+    candidates.first().copied().unwrap_or(0)
 }
 
-#[aoc(day3, part2)]
-pub fn solve_part2(_input: &[u32]) -> u32 {
-    unimplemented!()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(198));
+        assert_eq!(solve_part2(&input), Ok(230));
+    }
+
+    #[test]
+    fn leading_zero_column() {
+        let input = input_generator("0100\n0110\n0011").unwrap();
+        // gamma = 0110, epsilon = 1001
+        assert_eq!(solve_part1(&input), Ok(6 * 9));
+        // oxygen = 0110, co2 = 0011
+        assert_eq!(solve_part2(&input), Ok(6 * 3));
+    }
+
+    #[test]
+    fn reports_bad_line() {
+        let err = input_generator("00100\n0010\n00100").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 5));
+
+        let err = input_generator("00100\n00120").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 4));
+
+        let err = input_generator("\n0101").err().unwrap();
+        assert_eq!(
+            (err.line, err.column, err.expected),
+            (1, 1, "a binary number")
+        );
+        let err = input_generator("0101\n\n0101").err().unwrap();
+        assert_eq!((err.line, err.column), (2, 1));
+    }
 }
@@ -1,115 +1,183 @@
-use std::{collections::HashSet, fmt::Display};
+use std::fmt::Display;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::error::{numbered_lines, ParseError, SolveError};
+use crate::grid::Grid;
+
 type Number = u8;
 
+/// Parses the drawn numbers and the boards. All boards are square and as large as the first
+/// row says, up to 8 by 8.
 #[aoc_generator(day4)]
-pub fn input_generator(s: &str) -> Game {
-    let mut it = s.split_ascii_whitespace();
+pub fn input_generator(s: &str) -> Result<Game, ParseError> {
+    let mut lines = numbered_lines(s);
 
-    let draw = it
+    let (_, first) = lines
         .next()
-        .unwrap()
-        .split(",")
-        .filter_map(|s| s.parse().ok())
-        .collect();
+        .ok_or_else(|| ParseError::new(1, "", "the drawn numbers"))?;
+
+    let draw = first
+        .split(',')
+        .map(|n| n.parse().map_err(|_| ParseError::at(first, n, "a number")))
+        .collect::<Result<_, _>>()?;
 
     let mut boards = vec![];
+    let mut size = None;
+    let mut cells = vec![];
+    let mut last_line = 1;
+    for (line_no, line) in lines {
+        let tokens = line.split_ascii_whitespace().collect::<Vec<_>>();
+        if tokens.is_empty() {
+            continue;
+        }
 
-    let mut it = it.filter_map(|n| n.parse().ok());
+        let size = *size.get_or_insert(tokens.len().min(Board::MAX_SIZE));
+        if tokens.len() > size {
+            let expected = if size == Board::MAX_SIZE {
+                "at most 8 numbers per row"
+            } else {
+                "as many numbers as on the first board row"
+            };
+            return Err(ParseError::at(line, tokens[size], expected).on_line(line_no));
+        } else if tokens.len() < size {
+            let end = &line[line.len()..];
+            let err = ParseError::at(line, end, "as many numbers as on the first board row");
+            return Err(err.on_line(line_no));
+        }
 
-    loop {
-        if let Some(board) = Board::try_from_iter(&mut it) {
-            // println!("{}", board);
-            boards.push(board);
-        } else {
-            break;
+        for n in tokens {
+            let n = n
+                .parse()
+                .map_err(|_| ParseError::at(line, n, "a number").on_line(line_no))?;
+            cells.push(n);
+        }
+        last_line = line_no;
+
+        if cells.len() == size * size {
+            boards.push(Board(Grid::from_vec(
+                size,
+                size,
+                std::mem::take(&mut cells),
+            )));
         }
     }
 
-    Game { draw, boards }
+    if !cells.is_empty() {
+        return Err(
+            ParseError::new(1, "", "another row to complete the board").on_line(last_line + 1)
+        );
+    }
+
+    Ok(Game {
+        draw,
+        boards,
+        diagonals: false,
+    })
 }
 
 #[aoc(day4, part1)]
-pub fn solve_part1(input: &Game) -> u32 {
-    let mut game_state = input.start();
-
-    loop {
-        match game_state.draw() {
-            DrawResult::Bingo(winners) => break winners.first().unwrap().score,
-            DrawResult::NoBingo => continue,
-            DrawResult::End => panic!("No winner found"),
-        }
-    }
+pub fn solve_part1(input: &Game) -> Result<u32, SolveError> {
+    let winners = input.winners();
+    winners.first().map(|w| w.score).ok_or(SolveError::NoWinner)
 }
 
 #[aoc(day4, part2)]
-pub fn solve_part2(input: &Game) -> u32 {
-    let mut game_state = input.start();
-    let count = input.boards.len();
-    let mut all_winners: HashSet<_> = (0..count).collect();
-
-    loop {
-        match game_state.draw() {
-            DrawResult::Bingo(winners) => {
-                for winner in winners.iter() {
-                    all_winners.remove(&winner.board);
-                    // println!("{:?} {}", &all_winners, winner.score);
-                    if all_winners.is_empty() {
-                        return winner.score;
-                    }
-                }
-            }
-            DrawResult::End => panic!("No winner found"),
-            _ => {}
-        }
+pub fn solve_part2(input: &Game) -> Result<u32, SolveError> {
+    let winners = input.winners();
+    if winners.len() < input.boards.len() {
+        return Err(SolveError::NoWinner);
     }
+    winners.last().map(|w| w.score).ok_or(SolveError::NoWinner)
 }
 
 pub struct Game {
     draw: Vec<Number>,
     boards: Vec<Board>,
+    /// Whether completing one of the two diagonals wins as well
+    diagonals: bool,
 }
 
 impl Game {
+    /// Enables diagonal wins, which the puzzle doesn't count
+    pub fn with_diagonals(self, diagonals: bool) -> Game {
+        Game { diagonals, ..self }
+    }
+
+    /// The first win of every board in the order they happen. Boards that never win are
+    /// left out.
+    pub fn winners(&self) -> Vec<Winner> {
+        let mut game_state = self.start();
+        let mut won = vec![false; self.boards.len()];
+        let mut winners = vec![];
+
+        loop {
+            match game_state.draw() {
+                DrawResult::Bingo(new) => {
+                    for winner in new {
+                        if !std::mem::replace(&mut won[winner.board], true) {
+                            winners.push(winner);
+                        }
+                    }
+                }
+                DrawResult::NoBingo => continue,
+                DrawResult::End => return winners,
+            }
+        }
+    }
+
     fn start<'a>(&'a self) -> GameState<'a> {
         GameState {
             game: self,
             round: 0,
-            states: self.boards.iter().map(BoardState::new).collect(),
+            states: self
+                .boards
+                .iter()
+                .map(|board| BoardState::new(board, self.diagonals))
+                .collect(),
         }
     }
 }
 
-struct Board([Number; 25]);
+/// A square board of up to 8 by 8 numbers, so that the marked ones fit into a `u64`
+struct Board(Grid<Number>);
 
 impl Board {
-    fn try_from_iter(it: &mut impl Iterator<Item = Number>) -> Option<Board> {
-        let mut res = Board([0; 25]);
-        for i in 0..25 {
-            if let Some(val) = it.next() {
-                res.0[i] = val;
-            } else {
-                return None;
-            }
-        }
+    const MAX_SIZE: usize = 8;
+
+    fn size(&self) -> usize {
+        self.0.width()
+    }
+
+    fn row_mask(&self, row: usize) -> u64 {
+        let n = self.size();
+        ((1 << n) - 1) << (row * n)
+    }
+
+    fn column_mask(&self, col: usize) -> u64 {
+        let n = self.size();
+        (0..n).fold(0, |mask, row| mask | 1 << (row * n + col))
+    }
+
+    fn diag_mask(&self) -> u64 {
+        let n = self.size();
+        (0..n).fold(0, |mask, i| mask | 1 << (i * n + i))
+    }
 
-        Some(res)
+    fn antidiag_mask(&self) -> u64 {
+        let n = self.size();
+        (0..n).fold(0, |mask, i| mask | 1 << (i * n + n - 1 - i))
     }
 }
 
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in self.0.chunks(5) {
-            write!(
-                f,
-                "{:3} {:3} {:3} {:3} {:3}\n",
-                row[0], row[1], row[2], row[3], row[4]
-            )?;
+        for row in self.0.rows() {
+            let row = row.iter().map(|n| format!("{:3}", n)).collect::<Vec<_>>();
+            writeln!(f, "{}", row.join(" "))?;
         }
 
-        write!(f, "\n")
+        writeln!(f)
     }
 }
 
@@ -133,7 +201,7 @@ impl GameState<'_> {
                     });
                 }
             }
-            if winners.len() > 0 {
+            if !winners.is_empty() {
                 DrawResult::Bingo(winners)
             } else {
                 DrawResult::NoBingo
@@ -151,29 +219,37 @@ enum DrawResult {
     End,
 }
 
-#[derive(Debug, Clone)]
-struct Winner {
-    board: usize,
-    bingo: Bingo,
-    score: u32,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Winner {
+    /// Index of the board, in input order
+    pub board: usize,
+    pub bingo: Bingo,
+    /// Sum of the unmarked numbers times the number that was drawn last
+    pub score: u32,
 }
 
 #[derive(Clone, Copy)]
 struct BoardState<'a> {
-    state: u32,
+    /// One bit per cell in row-major order, set once its number was drawn
+    state: u64,
     board: &'a Board,
+    diagonals: bool,
 }
 
 impl BoardState<'_> {
-    fn new<'a>(board: &'a Board) -> BoardState<'a> {
-        BoardState { state: 0, board }
+    fn new(board: &Board, diagonals: bool) -> BoardState<'_> {
+        BoardState {
+            state: 0,
+            board,
+            diagonals,
+        }
     }
 
     fn sum_unmarked(&self) -> u32 {
         let mut res = 0;
-        for i in 0..25 {
+        for (i, &value) in self.board.0.iter().enumerate() {
             if 1 << i & self.state == 0 {
-                res += self.board.0[i] as u32;
+                res += value as u32;
             }
         }
         res
@@ -189,82 +265,158 @@ impl BoardState<'_> {
 
     fn set(&mut self, n: usize) -> Option<Bingo> {
         self.state |= 1 << n;
-        let col = n % 5;
-        let row = n / 5;
+        let size = self.board.size();
+        let (col, row) = (n % size, n / size);
         assert!(self.get(col, row));
 
-        // println!("{}", self);
-        self.check_row(row).or(self.check_column(col))
-        /* if col == row {
-            self.check_diag()?;
-        }
-        if 5 - col == row {
-            self.check_antidiag()?;
-        } */
-    }
-
-    // fn check_diag(&self) -> Option<Bingo> {
-    //     const MASK: u32 = 1 << (0 * 5 + 0)
-    //         | 1 << (1 * 5 + 1)
-    //         | 1 << (2 * 5 + 2)
-    //         | 1 << (3 * 5 + 3)
-    //         | 1 << (4 * 5 + 4);
-    //     (MASK & self.state == MASK).then(|| Bingo::Diag)
-    // }
-
-    // fn check_antidiag(&self) -> Option<Bingo> {
-    //     const MASK: u32 = 1 << (0 * 5 + 4)
-    //         | 1 << (1 * 5 + 3)
-    //         | 1 << (2 * 5 + 2)
-    //         | 1 << (3 * 5 + 1)
-    //         | 1 << (4 * 5 + 0);
-    //     (MASK & self.state == MASK).then(|| Bingo::AntiDiag)
-    // }
-
-    const fn check_column(&self, col: usize) -> Option<Bingo> {
-        const MASK: u32 = 1 << 0 * 5 | 1 << 1 * 5 | 1 << 2 * 5 | 1 << 3 * 5 | 1 << 4 * 5;
-        let mask = MASK << col;
-        // println!("Col: {}\nVal:  {:025b}\nMask: {:025b}\nComb: {:025b}\n", col, self.0, mask, self.0 & mask);
-        // println!("Comb == Mask: {}", self.0 & mask == mask);
-        if mask & self.state == mask {
-            Some(Bingo::Column(col))
-        } else {
-            None
-        }
-    }
-
-    const fn check_row(&self, row: usize) -> Option<Bingo> {
-        const MASK: u32 = 1 << 0 | 1 << 1 | 1 << 2 | 1 << 3 | 1 << 4;
-        let mask = MASK << row * 5;
-        if mask & self.state == mask {
+        let complete = |mask: u64| mask & self.state == mask;
+        if complete(self.board.row_mask(row)) {
             Some(Bingo::Row(row))
+        } else if complete(self.board.column_mask(col)) {
+            Some(Bingo::Column(col))
+        } else if self.diagonals && col == row && complete(self.board.diag_mask()) {
+            Some(Bingo::Diag)
+        } else if self.diagonals && col + row == size - 1 && complete(self.board.antidiag_mask()) {
+            Some(Bingo::AntiDiag)
         } else {
             None
         }
     }
 
-    const fn get(&self, col: usize, row: usize) -> bool {
-        1 << (row * 5 + col) & self.state != 0
+    fn get(&self, col: usize, row: usize) -> bool {
+        1 << (row * self.board.size() + col) & self.state != 0
     }
 }
 
 impl Display for BoardState<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in 0..5 {
-            for col in 0..5 {
-                write!(f, "{}", if self.get(row, col) { "  X" } else { "  ." })?;
+        for row in 0..self.board.size() {
+            for col in 0..self.board.size() {
+                write!(f, "{}", if self.get(col, row) { "  X" } else { "  ." })?;
             }
 
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
-        write!(f, "\n")
+        writeln!(f)
     }
 }
 
-#[derive(Debug, Clone)]
-enum Bingo {
-    // Diag,
-    // AntiDiag,
+/// The line that completed a board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bingo {
+    /// From the top left to the bottom right corner
+    Diag,
+    /// From the top right to the bottom left corner
+    AntiDiag,
     Row(usize),
     Column(usize),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7";
+
+    #[test]
+    fn example() {
+        let input = input_generator(EXAMPLE).unwrap();
+        assert_eq!(solve_part1(&input), Ok(4512));
+        assert_eq!(solve_part2(&input), Ok(1924));
+
+        let winners = input.winners();
+        let boards = winners.iter().map(|w| w.board).collect::<Vec<_>>();
+        assert_eq!(boards, [2, 0, 1]);
+        assert_eq!(winners[0].bingo, Bingo::Row(0));
+    }
+
+    #[test]
+    fn no_winner() {
+        let input = input_generator(
+            "1,2,3\n\n1 2 3 4 5\n6 7 8 9 10\n11 12 13 14 15\n16 17 18 19 20\n21 22 23 24 25",
+        )
+        .unwrap();
+        assert_eq!(solve_part1(&input), Err(SolveError::NoWinner));
+        assert_eq!(solve_part2(&input), Err(SolveError::NoWinner));
+    }
+
+    /// Plays until the first win and returns its kind
+    fn first_bingo(game: &Game) -> Option<Bingo> {
+        game.winners().first().map(|w| w.bingo.clone())
+    }
+
+    #[test]
+    fn diagonals() {
+        // Only the diagonals are complete after drawing all of these
+        let diag = "1,5,9,3,7\n\n1 2 3\n4 5 6\n7 8 9";
+        let input = input_generator(diag).unwrap();
+        assert_eq!(first_bingo(&input), None);
+        let input = input.with_diagonals(true);
+        assert_eq!(first_bingo(&input), Some(Bingo::Diag));
+        assert_eq!(solve_part1(&input), Ok(9 * (2 + 3 + 4 + 6 + 7 + 8)));
+
+        let input = input_generator("7,5,3,1\n\n1 2 3\n4 5 6\n7 8 9")
+            .unwrap()
+            .with_diagonals(true);
+        assert_eq!(first_bingo(&input), Some(Bingo::AntiDiag));
+        assert_eq!(solve_part1(&input), Ok(3 * (1 + 2 + 4 + 6 + 8 + 9)));
+
+        // In the example a diagonal completes well before the winning row
+        let input = input_generator(EXAMPLE).unwrap().with_diagonals(true);
+        assert_eq!(solve_part1(&input), Ok(494));
+    }
+
+    #[test]
+    fn board_sizes() {
+        let input = input_generator("4,1,3,2\n\n1 2\n3 4\n\n4 3\n2 1").unwrap();
+        assert_eq!(first_bingo(&input), Some(Bingo::Row(1)));
+        assert_eq!(solve_part2(&input), Ok(2 * 3));
+
+        let board = (0..8)
+            .map(|row| {
+                let row = (1..=8).map(|col| (row * 8 + col).to_string());
+                row.collect::<Vec<_>>().join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let column = (1..=8).map(|row| (row * 8).to_string());
+        let input = format!("{}\n\n{}", column.collect::<Vec<_>>().join(","), board);
+        let input = input_generator(&input).unwrap();
+        assert_eq!(first_bingo(&input), Some(Bingo::Column(7)));
+        assert_eq!(solve_part1(&input), Ok(64 * (64 * 65 / 2 - 8 * 36)));
+
+        let err = input_generator("1\n\n1 2 3 4 5 6 7 8 9").err().unwrap();
+        assert_eq!((err.line, err.column, err.text.as_str()), (3, 17, "9"));
+
+        let err = input_generator("1\n\n1 2 3\n4 5 6\n7 8").err().unwrap();
+        assert_eq!((err.line, err.column, err.text.as_str()), (5, 4, ""));
+        let err = input_generator("1\n\n1 2\n3 4 5").err().unwrap();
+        assert_eq!((err.line, err.column, err.text.as_str()), (4, 5, "5"));
+    }
+
+    #[test]
+    fn reports_incomplete_board() {
+        let err = input_generator("1,2,3\n\n1 2 3 4 5\n6 7 8 9 10")
+            .err()
+            .unwrap();
+        assert_eq!(err.line, 5);
+    }
+}
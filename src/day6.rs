@@ -1,44 +1,51 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::bignum::BigUint;
+use crate::error::{ParseError, SolveError};
+
+pub mod lanternfish;
+use lanternfish::Lanternfish;
+
 #[aoc_generator(day6)]
-pub fn input_generator(s: &str) -> Vec<u8> {
-    s.split(",").filter_map(|n| n.parse().ok()).collect()
+pub fn input_generator(s: &str) -> Result<Vec<u8>, ParseError> {
+    let line = s.trim_end();
+    line.split(',')
+        .map(|n| match n.parse() {
+            Ok(timer) if timer <= 8 => Ok(timer),
+            _ => Err(ParseError::at(line, n, "a timer between 0 and 8")),
+        })
+        .collect()
 }
 
 #[aoc(day6, part1)]
-pub fn solve_part1(input: &Vec<u8>) -> usize {
-    let mut fishes = input.clone();
-
-    for _ in 0..80 {
-        let mut to_add = 0;
-
-        for fish in fishes.iter_mut() {
-            if *fish == 0 {
-                *fish = 6;
-                to_add += 1
-            } else {
-                *fish -= 1;
-            }
-        }
-
-        for _ in 0..to_add {
-            fishes.push(8);
-        }
-    }
-
-    fishes.len()
+pub fn solve_part1(input: &[u8]) -> Result<BigUint, SolveError> {
+    Ok(Lanternfish::default().population(input))
 }
 
 #[aoc(day6, part2)]
-pub fn solve_part2(input: &Vec<u8>) -> usize {
-    let mut fishes = [0usize; 9];
-    for &i in input.iter() {
-        fishes[i as usize] += 1;
-    }
+pub fn solve_part2(input: &[u8]) -> Result<BigUint, SolveError> {
+    let model = Lanternfish {
+        days: 256,
+        ..Lanternfish::default()
+    };
+    Ok(model.population(input))
+}
 
-    for shift in 1..=256 {
-        fishes[(shift + 6) % 9] += fishes[(shift - 1) % 9];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example() {
+        let input = input_generator("3,4,3,1,2").unwrap();
+        assert_eq!(solve_part1(&input).unwrap().to_string(), "5934");
+        assert_eq!(solve_part2(&input).unwrap().to_string(), "26984457539");
     }
 
-    fishes.iter().sum()
+    #[test]
+    fn reports_bad_timer() {
+        let err = input_generator("3,4,13,1,2").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
+        assert_eq!(err.text, "13");
+    }
 }
@@ -0,0 +1,281 @@
+//! Population counts for arbitrarily many days. Fish are only counted per timer value, and the
+//! counts of a given day are advanced by exponentiating the matrix that maps them to those of
+//! the next.
+
+use crate::bignum::BigUint;
+
+/// The rules by which lanternfish multiply, the defaults are those of the puzzle
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lanternfish {
+    /// Timer of a fish right after it spawned a new one
+    pub reset_timer: u8,
+    /// Timer of a newly spawned fish
+    pub newborn_timer: u8,
+    pub days: u64,
+}
+
+impl Default for Lanternfish {
+    fn default() -> Self {
+        Lanternfish {
+            reset_timer: 6,
+            newborn_timer: 8,
+            days: 80,
+        }
+    }
+}
+
+type Matrix = Vec<Vec<BigUint>>;
+
+impl Lanternfish {
+    /// Number of different timer values, from 0 to the largest one of the rules or `initial`.
+    /// Fish starting out with a larger timer than the rules give simply count down for longer.
+    fn timers(&self, initial: &[u8]) -> usize {
+        let largest = initial.iter().copied().max().unwrap_or(0);
+        self.reset_timer.max(self.newborn_timer).max(largest) as usize + 1
+    }
+
+    /// Number of fish with each timer value
+    fn counts(&self, initial: &[u8]) -> Vec<BigUint> {
+        let mut counts = vec![0u64; self.timers(initial)];
+        for &timer in initial {
+            counts[timer as usize] += 1;
+        }
+        counts.into_iter().map(BigUint::from).collect()
+    }
+
+    /// Number of lanternfish after `days`, starting with one fish per entry of `initial`.
+    /// Needs O(log days) matrix products, the result is exact however large it gets.
+    pub fn population(&self, initial: &[u8]) -> BigUint {
+        let counts = self.counts(initial);
+        let step = power(&self.transition(counts.len()), self.days);
+
+        let mut total = BigUint::default();
+        for row in step.iter() {
+            for (entry, count) in row.iter().zip(counts.iter()) {
+                total += &(entry * count);
+            }
+        }
+        total
+    }
+
+    /// Population on every day from the initial one up to and including `days`, by advancing
+    /// the counts one day at a time
+    pub fn history(&self, initial: &[u8]) -> History {
+        History {
+            counts: self.counts(initial),
+            reset_timer: self.reset_timer as usize,
+            newborn_timer: self.newborn_timer as usize,
+            days_left: Some(self.days),
+        }
+    }
+
+    /// Maps the timer counts of one day to those of the next. Timers count down by one, fish
+    /// at 0 go back to the reset timer and spawn a new one with the newborn timer.
+    fn transition(&self, n: usize) -> Matrix {
+        let mut m = vec![vec![BigUint::default(); n]; n];
+        for timer in 0..n - 1 {
+            m[timer][timer + 1] = BigUint::from(1u64);
+        }
+        m[self.reset_timer as usize][0] += &BigUint::from(1u64);
+        m[self.newborn_timer as usize][0] += &BigUint::from(1u64);
+        m
+    }
+}
+
+/// Number of lanternfish after `days` under the puzzle's rules
+pub fn population(initial: &[u8], days: u64) -> BigUint {
+    Lanternfish {
+        days,
+        ..Lanternfish::default()
+    }
+    .population(initial)
+}
+
+/// Iterator over the daily population, see `Lanternfish::history`
+pub struct History {
+    counts: Vec<BigUint>,
+    reset_timer: usize,
+    newborn_timer: usize,
+    /// `None` once the last day was returned
+    days_left: Option<u64>,
+}
+
+impl Iterator for History {
+    type Item = BigUint;
+
+    fn next(&mut self) -> Option<BigUint> {
+        let days_left = self.days_left?;
+        let total = self
+            .counts
+            .iter()
+            .fold(BigUint::default(), |acc, n| &acc + n);
+
+        self.days_left = days_left.checked_sub(1);
+        if self.days_left.is_some() {
+            let spawning = std::mem::take(&mut self.counts[0]);
+            self.counts.rotate_left(1);
+            self.counts[self.reset_timer] += &spawning;
+            self.counts[self.newborn_timer] += &spawning;
+        }
+
+        Some(total)
+    }
+}
+
+fn identity(n: usize) -> Matrix {
+    let mut m = vec![vec![BigUint::default(); n]; n];
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] = BigUint::from(1u64);
+    }
+    m
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let n = a.len();
+    let mut res = vec![vec![BigUint::default(); n]; n];
+    for i in 0..n {
+        for k in 0..n {
+            // Most entries of the early powers are zero
+            if a[i][k].is_zero() {
+                continue;
+            }
+            for j in 0..n {
+                if !b[k][j].is_zero() {
+                    res[i][j] += &(&a[i][k] * &b[k][j]);
+                }
+            }
+        }
+    }
+    res
+}
+
+/// Square and multiply
+fn power(m: &Matrix, mut exp: u64) -> Matrix {
+    let mut res = identity(m.len());
+    let mut base = m.clone();
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = multiply(&res, &base);
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = multiply(&base, &base);
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [u8; 5] = [3, 4, 3, 1, 2];
+
+    fn last_of_history(model: &Lanternfish, initial: &[u8]) -> BigUint {
+        model.history(initial).last().unwrap()
+    }
+
+    #[test]
+    fn matches_history() {
+        assert_eq!(population(&EXAMPLE, 0).to_string(), "5");
+        assert_eq!(population(&EXAMPLE, 18).to_string(), "26");
+        assert_eq!(population(&EXAMPLE, 256).to_string(), "26984457539");
+        for days in [1, 7, 9, 100, 999, 1000, 2021] {
+            let model = Lanternfish {
+                days,
+                ..Lanternfish::default()
+            };
+            assert_eq!(
+                model.population(&EXAMPLE),
+                last_of_history(&model, &EXAMPLE),
+                "{} days",
+                days
+            );
+        }
+        assert!(population(&[], 1000).is_zero());
+    }
+
+    #[test]
+    fn history() {
+        let model = Lanternfish {
+            days: 18,
+            ..Lanternfish::default()
+        };
+        let history = model.history(&EXAMPLE).map(|n| n.to_string());
+        assert_eq!(
+            history.collect::<Vec<_>>()[..6],
+            ["5", "5", "6", "7", "9", "10"]
+        );
+        assert_eq!(model.history(&EXAMPLE).count(), 19);
+    }
+
+    #[test]
+    fn custom_timers() {
+        // Every fish spawns each day and the offspring is ready after one, which gives the
+        // Fibonacci numbers
+        let model = Lanternfish {
+            reset_timer: 0,
+            newborn_timer: 1,
+            days: 10,
+        };
+        let history = model.history(&[0]).map(|n| n.to_string());
+        assert_eq!(
+            history.collect::<Vec<_>>(),
+            ["1", "2", "3", "5", "8", "13", "21", "34", "55", "89", "144"]
+        );
+        assert_eq!(model.population(&[0]).to_string(), "144");
+
+        // Both timers equal, including the degenerate case of doubling every day
+        for &(timer, days) in [(2, 50), (0, 70)].iter() {
+            let model = Lanternfish {
+                reset_timer: timer,
+                newborn_timer: timer,
+                days,
+            };
+            assert_eq!(model.population(&[0, 0]), last_of_history(&model, &[0, 0]));
+        }
+        let doubling = Lanternfish {
+            reset_timer: 0,
+            newborn_timer: 0,
+            days: 70,
+        };
+        assert_eq!(doubling.population(&[0]), BigUint::from(1u128 << 70));
+    }
+
+    #[test]
+    fn initial_timers_above_the_rules() {
+        // Fish counting down from 8 only join the 0-1-2-3 cycle after a few days
+        let model = Lanternfish {
+            reset_timer: 2,
+            newborn_timer: 3,
+            days: 9,
+        };
+        let history = model.history(&[3, 4, 8]).map(|n| n.to_string());
+        assert_eq!(
+            history.collect::<Vec<_>>(),
+            ["3", "3", "3", "3", "4", "5", "5", "6", "8", "10"]
+        );
+        for days in [0, 1, 9, 50, 123] {
+            let model = Lanternfish {
+                days,
+                ..model.clone()
+            };
+            assert_eq!(
+                model.population(&[3, 4, 8]),
+                last_of_history(&model, &[3, 4, 8])
+            );
+        }
+    }
+
+    #[test]
+    fn many_days() {
+        let model = Lanternfish {
+            days: 100_000,
+            ..Lanternfish::default()
+        };
+        let n = model.population(&EXAMPLE);
+        assert_eq!(n, last_of_history(&model, &EXAMPLE));
+        // Grows by a factor of about 1.091 a day
+        assert!((12_500..12_600).contains(&n.bits()));
+    }
+}
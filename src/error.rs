@@ -0,0 +1,146 @@
+use std::{error::Error, fmt::Display};
+
+/// Malformed puzzle input, pointing at the first offending piece of text. Lines and columns are
+/// 1-based, like in an editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub expected: &'static str,
+}
+
+impl ParseError {
+    /// Creates an error on the first line, for parsers that only see a single line. Use
+    /// `on_line` to move it to the right place afterwards.
+    pub fn new(column: usize, text: &str, expected: &'static str) -> Self {
+        ParseError {
+            line: 1,
+            column,
+            text: text.to_owned(),
+            expected,
+        }
+    }
+
+    /// Creates an error for `token`, which has to be a subslice of `line`
+    pub fn at(line: &str, token: &str, expected: &'static str) -> Self {
+        Self::new(column(line, token), token, expected)
+    }
+
+    pub fn on_line(self, line: usize) -> Self {
+        ParseError { line, ..self }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found ",
+            self.line, self.column, self.expected
+        )?;
+        if self.text.is_empty() {
+            write!(f, "nothing")
+        } else {
+            write!(f, "`{}`", self.text)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Iterates over the lines of `s` together with their 1-based line numbers
+pub fn numbered_lines(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.lines().enumerate().map(|(i, l)| (i + 1, l))
+}
+
+/// 1-based column at which `token` starts in `line`, `token` has to be a subslice of `line`.
+/// Other tokens are a bug in the caller, release builds fall back to the first occurrence of
+/// the text.
+pub fn column(line: &str, token: &str) -> usize {
+    let offset = (token.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+    if offset <= line.len() && token.len() <= line.len() - offset {
+        return offset + 1;
+    }
+
+    debug_assert!(false, "`{}` is not part of `{}`", token, line);
+    line.find(token).map_or(1, |pos| pos + 1)
+}
+
+/// A puzzle input that parsed fine but doesn't admit an answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// There is nothing to compute the answer from
+    EmptyInput,
+    /// All numbers were drawn but (not every) board won
+    NoWinner,
+    /// A cell holds a value outside of the range the puzzle allows
+    OutOfRange { x: usize, y: usize, value: i64 },
+    /// A node the puzzle relies on is not part of the input
+    MissingNode(&'static str),
+    /// The answer does not fit into the result type
+    Overflow,
+    /// A simulation returns to an earlier state without reaching the one it looks for. It is
+    /// in a loop of `length` steps from step `start` on.
+    Cycle { start: usize, length: usize },
+    /// A simulation didn't reach the state it looks for within the given number of steps
+    StepLimit(usize),
+    /// Two large caves are connected, so paths can go back and forth between them forever
+    UnboundedPaths { from: String, to: String },
+    /// The 1-based `command` would take the submarine above the surface, to `depth`
+    AboveSurface { command: usize, depth: i64 },
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::EmptyInput => write!(f, "no input to compute the answer from"),
+            SolveError::NoWinner => write!(f, "all numbers drawn without a winner"),
+            SolveError::OutOfRange { x, y, value } => {
+                write!(f, "cell ({}, {}) is out of range: {}", x, y, value)
+            }
+            SolveError::MissingNode(node) => write!(f, "node `{}` not found", node),
+            SolveError::Overflow => write!(f, "the answer does not fit into the result type"),
+            SolveError::Cycle { start, length } => write!(
+                f,
+                "enters cycle of length {} at step {} without synchronizing",
+                length, start
+            ),
+            SolveError::StepLimit(steps) => {
+                write!(f, "no synchronization within {} steps", steps)
+            }
+            SolveError::UnboundedPaths { from, to } => write!(
+                f,
+                "large caves `{}` and `{}` are connected, there is no end to the paths",
+                from, to
+            ),
+            SolveError::AboveSurface { command, depth } => write!(
+                f,
+                "command {} takes the submarine above the surface, to depth {}",
+                command, depth
+            ),
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns() {
+        let line = "forward 5";
+        assert_eq!(column(line, &line[8..]), 9);
+        assert_eq!(column(line, &line[9..]), 10);
+        assert_eq!(ParseError::at(line, &line[..7], "a verb").column, 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is not part of")]
+    fn foreign_token() {
+        column("forward 5", "5");
+    }
+}
@@ -0,0 +1,326 @@
+use std::ops::{Index, IndexMut};
+
+use crate::error::{numbered_lines, ParseError};
+
+/// A position on a grid, `(x, y)` with `x` the column and `y` the row, both counted from the
+/// top left corner
+pub type Point = (usize, usize);
+
+/// Offsets to the horizontally and vertically adjacent points
+pub const NEIGHBOURS4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+/// Offsets to all adjacent points, including diagonals
+pub const NEIGHBOURS8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+/// Offsets to the 6 neighbours of a hexagonal grid in axial coordinates, where every row is
+/// shifted half a cell to the left of the one above
+pub const NEIGHBOURS6: [(isize, isize); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+
+/// A rectangular grid of cells, stored row by row
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid from its cells in row-major order, `data` has to hold exactly
+    /// `width * height` of them
+    pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Self {
+        assert_eq!(
+            data.len(),
+            width * height,
+            "grid data doesn't match its size"
+        );
+        Grid {
+            data,
+            width,
+            height,
+        }
+    }
+
+    /// Parses a grid with one character per cell. All lines need to be equally long, `cell`
+    /// returns `None` for characters that aren't valid cells. Like everywhere else, errors
+    /// count columns in bytes.
+    pub fn parse(
+        s: &str,
+        expected: &'static str,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut data = vec![];
+        let mut width = None;
+        let mut height = 0;
+
+        for (line_no, line) in numbered_lines(s) {
+            let mut line_width = 0;
+            for (pos, c) in line.char_indices() {
+                let value = cell(c).ok_or_else(|| {
+                    let c = &line[pos..pos + c.len_utf8()];
+                    ParseError::at(line, c, expected).on_line(line_no)
+                })?;
+                data.push(value);
+                line_width += 1;
+            }
+
+            let width = *width.get_or_insert(line_width);
+            if line_width != width {
+                let pos = line
+                    .char_indices()
+                    .nth(width)
+                    .map_or(line.len(), |(pos, _)| pos);
+                return Err(
+                    ParseError::at(line, &line[pos..], "a line as long as the first one")
+                        .on_line(line_no),
+                );
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            data,
+            width: width.unwrap_or(0),
+            height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn contains(&self, (x, y): Point) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Index into the row-major cell data
+    pub fn index_of(&self, p: Point) -> Option<usize> {
+        self.contains(p).then(|| p.0 + p.1 * self.width)
+    }
+
+    /// Inverse of `index_of`
+    pub fn point_of(&self, index: usize) -> Point {
+        (index % self.width, index / self.width)
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.index_of(p).map(|idx| &self.data[idx])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.index_of(p).map(move |idx| &mut self.data[idx])
+    }
+
+    /// Moves `p` by `delta`, if the result is still on the grid
+    pub fn offset(&self, p: Point, delta: (isize, isize)) -> Option<Point> {
+        let x = p.0.checked_add_signed(delta.0)?;
+        let y = p.1.checked_add_signed(delta.1)?;
+        self.contains((x, y)).then_some((x, y))
+    }
+
+    /// Moves `p` by `delta`, wrapping around the edges as if the grid was a torus. Only an
+    /// empty grid has no point to end up on.
+    pub fn offset_wrapping(&self, p: Point, delta: (isize, isize)) -> Option<Point> {
+        if self.is_empty() {
+            return None;
+        }
+        let wrap = |v: usize, d: isize, n: usize| (v as isize + d).rem_euclid(n as isize) as usize;
+        Some((
+            wrap(p.0, delta.0, self.width),
+            wrap(p.1, delta.1, self.height),
+        ))
+    }
+
+    /// Cell at `p`, wrapping around the edges as if the grid was a torus
+    pub fn get_wrapping(&self, p: (isize, isize)) -> Option<&T> {
+        self.offset_wrapping((0, 0), p).map(|p| &self[p])
+    }
+
+    /// The up to 4 horizontally and vertically adjacent points
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOURS4.iter().filter_map(move |&d| self.offset(p, d))
+    }
+
+    /// The up to 8 adjacent points, including diagonals
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOURS8.iter().filter_map(move |&d| self.offset(p, d))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.data[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` doesn't accept 0, an empty grid has no rows either way
+        self.data.chunks(self.width.max(1))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} out of range", x);
+        self.data[x..].iter().step_by(self.width)
+    }
+
+    /// All cells in row-major order
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.data.iter_mut()
+    }
+
+    /// All points in row-major order
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            data: self.data.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self::from_vec(width, height, vec![value; width * height])
+    }
+
+    /// Mirrors the grid along its main diagonal, so rows become columns
+    pub fn transpose(&self) -> Self {
+        let data = (0..self.width)
+            .flat_map(|x| self.column(x).cloned())
+            .collect();
+        Grid {
+            data,
+            width: self.height,
+            height: self.width,
+        }
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: Point) -> &T {
+        match self.index_of(p) {
+            Some(idx) => &self.data[idx],
+            None => panic!(
+                "{:?} out of range for {}x{} grid",
+                p, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, p: Point) -> &mut T {
+        match self.index_of(p) {
+            Some(idx) => &mut self.data[idx],
+            None => panic!(
+                "{:?} out of range for {}x{} grid",
+                p, self.width, self.height
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(s: &str) -> Result<Grid<u32>, ParseError> {
+        Grid::parse(s, "a digit", |c| c.to_digit(10))
+    }
+
+    #[test]
+    fn parse_rectangular() {
+        let grid = digits("123\n456").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 0)], 3);
+        assert_eq!(grid[(0, 1)], 4);
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, 2)), None);
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![2, 5]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = digits("123\n4x6").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 2, "x"));
+
+        let err = digits("123\n4567").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 4, "7"));
+
+        let err = digits("123\n45").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 3, ""));
+
+        // Columns count bytes, `é` takes two
+        let letters = |s| Grid::parse(s, "a letter", |c: char| c.is_alphabetic().then_some(c));
+        let err = letters("abc\néb1").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 4, "1"));
+        let err = letters("ab\nébc").unwrap_err();
+        assert_eq!((err.line, err.column, err.text.as_str()), (2, 4, "c"));
+    }
+
+    #[test]
+    fn neighbours() {
+        let grid = digits("123\n456\n789").unwrap();
+        let at = |points: Vec<Point>| points.iter().map(|&p| grid[p]).collect::<Vec<_>>();
+
+        assert_eq!(at(grid.neighbours4((0, 0)).collect()), vec![2, 4]);
+        assert_eq!(at(grid.neighbours4((1, 1)).collect()), vec![2, 4, 6, 8]);
+        assert_eq!(at(grid.neighbours8((2, 2)).collect()), vec![5, 6, 8]);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+    }
+
+    #[test]
+    fn wrapping() {
+        let grid = digits("123\n456").unwrap();
+        assert_eq!(grid.get_wrapping((-1, 0)), Some(&3));
+        assert_eq!(grid.get_wrapping((3, -1)), Some(&4));
+        assert_eq!(grid.get_wrapping((-4, 5)), Some(&6));
+
+        let empty = digits("").unwrap();
+        assert_eq!(empty.get_wrapping((1, 1)), None);
+        assert_eq!(empty.offset_wrapping((0, 0), (-1, 0)), None);
+    }
+
+    #[test]
+    fn transpose() {
+        let grid = digits("123\n456").unwrap();
+        let transposed = grid.transpose();
+        assert_eq!((transposed.width(), transposed.height()), (2, 3));
+        assert_eq!(
+            transposed.rows().collect::<Vec<_>>(),
+            vec![&[1, 4], &[2, 5], &[3, 6]]
+        );
+        assert_eq!(transposed.transpose(), grid);
+    }
+}
@@ -1,16 +1,23 @@
 use aoc_runner_derive::aoc_lib;
 
-mod day1;
-mod day2;
+pub mod bignum;
+pub mod error;
+pub mod grid;
+
+pub mod day1;
+pub mod day2;
 mod day3;
-mod day4;
+pub mod day4;
 
-mod day6;
+pub mod day6;
 
 mod day10;
-mod day11;
+pub mod day11;
 mod day12;
 
-mod day18;
+pub mod day18;
+
+#[cfg(test)]
+mod regression;
 
 aoc_lib! {year = 2021}
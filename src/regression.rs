@@ -0,0 +1,55 @@
+//! Checks our answers for the real puzzle inputs, so refactorings can't silently break them.
+//!
+//! The inputs are personal, so they aren't part of the repository. A day is only checked if both
+//! `input/2021/dayN.txt` (where `cargo aoc` puts them) and `answers/dayN.txt` exist. The answers
+//! file holds the answer to part 1 on the first and the one to part 2 on the second line, a
+//! missing line skips that part.
+
+use std::{fs, path::Path};
+
+fn load(day: u32) -> Option<(String, Vec<String>)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let input = fs::read_to_string(root.join(format!("input/2021/day{}.txt", day))).ok()?;
+    let answers = fs::read_to_string(root.join(format!("answers/day{}.txt", day))).ok()?;
+    let answers = answers.lines().map(|l| l.trim().to_owned()).collect();
+
+    Some((input, answers))
+}
+
+macro_rules! regression {
+    ($($day:ident => $num:literal),* $(,)?) => {
+        $(
+            #[test]
+            fn $day() {
+                let (input, answers) = match load($num) {
+                    Some(loaded) => loaded,
+                    None => return,
+                };
+
+                // Same as the runner, which strips trailing newlines before calling generators
+                let input = crate::$day::input_generator(input.trim_end_matches('\n')).unwrap();
+
+                if let Some(expected) = answers.get(0).filter(|a| !a.is_empty()) {
+                    let answer = crate::$day::solve_part1(&input).unwrap();
+                    assert_eq!(&answer.to_string(), expected, "day {} part 1", $num);
+                }
+                if let Some(expected) = answers.get(1).filter(|a| !a.is_empty()) {
+                    let answer = crate::$day::solve_part2(&input).unwrap();
+                    assert_eq!(&answer.to_string(), expected, "day {} part 2", $num);
+                }
+            }
+        )*
+    };
+}
+
+regression! {
+    day1 => 1,
+    day2 => 2,
+    day3 => 3,
+    day4 => 4,
+    day6 => 6,
+    day10 => 10,
+    day11 => 11,
+    day12 => 12,
+    day18 => 18,
+}